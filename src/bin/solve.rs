@@ -0,0 +1,167 @@
+//! Binary that searches for solutions of levels in the LDtk project.
+//!
+//! Run it with `cargo run --bin solve -- [--weight W] [--max-states N] [LEVEL]...`, where each
+//! level is either a 1-based level number or a level identifier.
+//! All levels are solved if no levels are given.
+//!
+//! Solutions are the shortest possible by default.
+//! With `--weight`, the search trusts its estimate of the remaining grave-actions W times more,
+//! finding solutions of hard levels much faster, but they may be up to W times longer.
+//! The solver gives up on a level after visiting [MAX_STATES] unique states, unless `--max-states`
+//! says otherwise.
+//!
+//! Levels with a par must have a solution no longer than their par, or the par is reported as
+//! unreachable.
+//! Pars are the lengths of the solutions found with `--weight 3 --max-states 20000000`, which is
+//! how CI runs this binary.
+//!
+//! The process exits with a failure code if any level is unsolvable or its par is unreachable, so
+//! it can be used to catch broken levels after edits.
+//! If the solver gives up on a level without finding a solution either way, it exits with
+//! [GAVE_UP_EXIT_CODE] instead.
+use bevy_ecs_ldtk::ldtk::{LdtkJson, Level};
+use std::{fs::File, io::BufReader, path::Path, process::ExitCode};
+use willos_graveyard::graveyard::{
+    move_counter::level_par,
+    simulation::GraveyardSimulation,
+    solver::{solve, Solution},
+};
+
+/// Path to the LDtk project, relative to the crate root.
+const PROJECT_PATH: &str = "assets/levels/willos-graveyard.ldtk";
+
+/// Default number of unique states the solver may visit per level before giving up.
+const MAX_STATES: usize = 2_000_000;
+
+/// Default weight of the search's estimate, which finds the shortest solutions.
+const WEIGHT: u32 = 1;
+
+/// Exit code used when no level is unsolvable, but the solver gave up on some of them.
+const GAVE_UP_EXIT_CODE: u8 = 2;
+
+fn load_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, Box<dyn std::error::Error>> {
+    Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+}
+
+/// Loads the layer instances of levels stored in external level files.
+fn load_level(
+    project_directory: &Path,
+    level: &Level,
+) -> Result<Level, Box<dyn std::error::Error>> {
+    match &level.external_rel_path {
+        Some(external_rel_path) => load_json(&project_directory.join(external_rel_path)),
+        None => Ok(level.clone()),
+    }
+}
+
+fn main() -> ExitCode {
+    let project_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(PROJECT_PATH);
+    let project_directory = project_path
+        .parent()
+        .expect("project path should have a parent directory");
+
+    let project: LdtkJson = match load_json(&project_path) {
+        Ok(project) => project,
+        Err(e) => {
+            eprintln!("unable to load {}: {e}", project_path.display());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut weight = WEIGHT;
+    let mut max_states = MAX_STATES;
+    let mut level_args: Vec<String> = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--weight" {
+            match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) if value > 0 => weight = value,
+                _ => {
+                    eprintln!("--weight expects a positive number");
+                    return ExitCode::FAILURE;
+                }
+            }
+        } else if arg == "--max-states" {
+            match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => max_states = value,
+                None => {
+                    eprintln!("--max-states expects a number of states");
+                    return ExitCode::FAILURE;
+                }
+            }
+        } else {
+            level_args.push(arg);
+        }
+    }
+
+    let mut success = true;
+    let mut gave_up = false;
+
+    for (i, level) in project.levels.iter().enumerate() {
+        let level_num = i + 1;
+
+        if !level_args.is_empty()
+            && !level_args
+                .iter()
+                .any(|arg| *arg == level.identifier || arg.parse::<usize>().ok() == Some(level_num))
+        {
+            continue;
+        }
+
+        let simulation = match load_level(project_directory, level)
+            .and_then(|level| Ok(GraveyardSimulation::from_level(&level)?))
+        {
+            Ok(simulation) => simulation,
+            Err(e) => {
+                println!(
+                    "#{level_num} {}: unable to load level: {e}",
+                    level.identifier
+                );
+                success = false;
+                continue;
+            }
+        };
+
+        match solve(&simulation, weight, max_states) {
+            Solution::Solved(grave_ids) => {
+                let keys: Vec<&str> = grave_ids.iter().map(|g| g.ldtk_identifier()).collect();
+                println!(
+                    "#{level_num} {}: solved in {} moves: {}",
+                    level.identifier,
+                    grave_ids.len(),
+                    keys.join(" ")
+                );
+
+                if let Some(par) = level_par(level) {
+                    if grave_ids.len() > par as usize {
+                        println!(
+                            "#{level_num} {}: par of {par} is shorter than the solution",
+                            level.identifier
+                        );
+                        success = false;
+                    }
+                }
+            }
+            Solution::Unsolvable => {
+                println!("#{level_num} {}: no solution exists", level.identifier);
+                success = false;
+            }
+            Solution::GaveUp => {
+                println!(
+                    "#{level_num} {}: gave up after {max_states} states",
+                    level.identifier
+                );
+                gave_up = true;
+            }
+        }
+    }
+
+    if !success {
+        ExitCode::FAILURE
+    } else if gave_up {
+        ExitCode::from(GAVE_UP_EXIT_CODE)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,55 @@
+//! Plugin providing spawning logic for coffins, which are pushable blocks spanning several cells.
+//!
+//! Coffins are sized by the width and height of their LDtk entity.
+//! Unlike gravestones, they aren't tied to grave-actions, can't be exorcised, and don't satisfy
+//! goals.
+//! See [Footprint] for how they move.
+//!
+//! Coffins may be made heavier with their optional "Weight" field, see [Weight].
+use crate::{
+    history::History,
+    sokoban::{Footprint, SokobanBlock, Weight},
+    UNIT_LENGTH,
+};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+/// Plugin providing spawning logic for coffins, which are pushable blocks spanning several cells.
+pub struct CoffinPlugin;
+
+impl Plugin for CoffinPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<CoffinBundle>("Coffin");
+    }
+}
+
+/// The [Footprint] of a coffin, covering every cell of its LDtk entity.
+pub fn coffin_footprint(entity_instance: &EntityInstance) -> Footprint {
+    Footprint::rectangle(IVec2::new(entity_instance.width, entity_instance.height) / UNIT_LENGTH)
+}
+
+/// The [Weight] of a coffin, as defined by the optional "Weight" field of its LDtk entity.
+pub fn coffin_weight(entity_instance: &EntityInstance) -> Weight {
+    entity_instance
+        .get_maybe_int_field("Weight")
+        .ok()
+        .copied()
+        .flatten()
+        .and_then(|weight| u32::try_from(weight).ok())
+        .map_or(Weight::default(), Weight)
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+struct CoffinBundle {
+    #[grid_coords]
+    grid_coords: GridCoords,
+    history: History<GridCoords>,
+    #[with(SokobanBlock::new_dynamic)]
+    sokoban_block: SokobanBlock,
+    #[with(coffin_footprint)]
+    footprint: Footprint,
+    #[with(coffin_weight)]
+    weight: Weight,
+    #[sprite_sheet_bundle]
+    sprite_sheet_bundle: SpriteSheetBundle,
+}
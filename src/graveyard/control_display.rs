@@ -1,7 +1,11 @@
 //! Plugin providing functionality for the graveyard UI element showing the current controls.
 use crate::{
     camera::PlayZonePortion,
-    graveyard::{gravestone::GraveId, movement_table::MovementTable},
+    graveyard::{
+        gravestone::GraveId,
+        movement_table::{MovementTable, DIRECTION_ORDER},
+    },
+    sokoban::Direction,
     ui::{
         action::UiAction,
         icon_button::{IconButton, IconButtonBundle, IconButtonSet},
@@ -34,7 +38,7 @@ impl Plugin for ControlDisplayPlugin {
 struct ControlDisplay;
 
 /// Asset collection for loading/storing assets relevant to the control display.
-#[derive(Clone, Debug, AssetCollection, Resource)]
+#[derive(Clone, Debug, Default, AssetCollection, Resource)]
 pub struct ControlDisplayAssets {
     #[asset(texture_atlas(tile_size_x = 64., tile_size_y = 64., columns = 4, rows = 4))]
     #[asset(path = "textures/movement-table-actions.png")]
@@ -42,6 +46,58 @@ pub struct ControlDisplayAssets {
     #[asset(texture_atlas(tile_size_x = 64., tile_size_y = 64., columns = 3, rows = 1))]
     #[asset(path = "textures/graveyard-actions.png")]
     graveyard_actions: Handle<TextureAtlas>,
+    #[asset(path = "textures/up.png")]
+    up: Handle<Image>,
+    #[asset(path = "textures/left.png")]
+    left: Handle<Image>,
+    #[asset(path = "textures/down.png")]
+    down: Handle<Image>,
+    #[asset(path = "textures/right.png")]
+    right: Handle<Image>,
+    #[asset(path = "textures/up-left.png")]
+    up_left: Handle<Image>,
+    #[asset(path = "textures/up-right.png")]
+    up_right: Handle<Image>,
+    #[asset(path = "textures/down-left.png")]
+    down_left: Handle<Image>,
+    #[asset(path = "textures/down-right.png")]
+    down_right: Handle<Image>,
+}
+
+impl ControlDisplayAssets {
+    /// Icon for a grave-action whose rank and file moves are in the given directions.
+    ///
+    /// Grave-actions with a no-op rank or file only show the direction of the other move.
+    /// The movement table atlas only covers orthogonal directions, so grave-actions combining a
+    /// diagonal rank and file show no icon.
+    fn grave_action_icon(&self, rank: Option<Direction>, file: Option<Direction>) -> IconButton {
+        let order = |direction| DIRECTION_ORDER.iter().position(|d| *d == direction);
+
+        let direction_image = |direction| match direction {
+            Direction::Up => self.up.clone(),
+            Direction::Left => self.left.clone(),
+            Direction::Down => self.down.clone(),
+            Direction::Right => self.right.clone(),
+            Direction::UpLeft => self.up_left.clone(),
+            Direction::UpRight => self.up_right.clone(),
+            Direction::DownLeft => self.down_left.clone(),
+            Direction::DownRight => self.down_right.clone(),
+        };
+
+        match (rank, file) {
+            (Some(rank), Some(file)) => match (order(rank), order(file)) {
+                (Some(rank), Some(file)) => IconButton::AtlasImageIcon(UiAtlasImage {
+                    texture_atlas: self.movement_table_actions.clone(),
+                    index: rank * DIRECTION_ORDER.len() + file,
+                }),
+                _ => IconButton::NoIcon,
+            },
+            (Some(direction), None) | (None, Some(direction)) => {
+                IconButton::ImageIcon(UiImage::new(direction_image(direction)))
+            }
+            (None, None) => IconButton::NoIcon,
+        }
+    }
 }
 
 fn spawn_control_display(
@@ -217,19 +273,10 @@ fn update_grave_action_buttons(
 ) {
     for movement_table in movement_tables.iter() {
         for (mut icon_button, action) in &mut grave_action_buttons {
-            *icon_button = match movement_table
-                .table
-                .iter()
-                .flat_map(|row| row.iter())
-                .enumerate()
-                .find(|(_, g)| **g == Some(**action))
-            {
-                Some((index, _)) => IconButton::AtlasImageIcon(UiAtlasImage {
-                    texture_atlas: assets.movement_table_actions.clone(),
-                    index,
-                }),
-                None => IconButton::NoIcon,
-            }
+            *icon_button = assets.grave_action_icon(
+                movement_table.rank_directions(**action).next(),
+                movement_table.file_directions(**action).next(),
+            );
         }
     }
 }
@@ -237,6 +284,7 @@ fn update_grave_action_buttons(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::graveyard::movement_table::MovementTableLayout;
     use rand::prelude::*;
 
     fn app_setup() -> App {
@@ -254,6 +302,14 @@ mod tests {
         let control_display_assets = ControlDisplayAssets {
             movement_table_actions: Handle::weak_from_u128(rng.gen()),
             graveyard_actions: Handle::weak_from_u128(rng.gen()),
+            up: Handle::weak_from_u128(rng.gen()),
+            left: Handle::weak_from_u128(rng.gen()),
+            down: Handle::weak_from_u128(rng.gen()),
+            right: Handle::weak_from_u128(rng.gen()),
+            up_left: Handle::weak_from_u128(rng.gen()),
+            up_right: Handle::weak_from_u128(rng.gen()),
+            down_left: Handle::weak_from_u128(rng.gen()),
+            down_right: Handle::weak_from_u128(rng.gen()),
         };
 
         app.insert_resource(control_display_assets.clone());
@@ -264,17 +320,18 @@ mod tests {
     fn spawn_movement_table(app: &mut App) -> Entity {
         app.world
             .spawn(MovementTable {
-                table: [
-                    [
+                table: vec![
+                    vec![
                         Some(GraveId::North),
                         Some(GraveId::Northwest),
                         None,
                         Some(GraveId::Northeast),
                     ],
-                    [None, Some(GraveId::West), None, None],
-                    [None, None, Some(GraveId::South), None],
-                    [None, None, None, Some(GraveId::East)],
+                    vec![None, Some(GraveId::West), None, None],
+                    vec![None, None, Some(GraveId::South), None],
+                    vec![None, None, None, Some(GraveId::East)],
                 ],
+                ..default()
             })
             .id()
     }
@@ -433,4 +490,77 @@ mod tests {
             &IconButton::NoIcon,
         );
     }
+
+    #[test]
+    fn grave_id_buttons_follow_table_layout() {
+        let mut app = app_setup();
+        let assets = asset_setup(&mut app);
+
+        // 2x3 table with a no-op rank and a repeated file direction
+        app.world.spawn(MovementTable {
+            table: vec![
+                vec![Some(GraveId::North), None, Some(GraveId::West)],
+                vec![None, Some(GraveId::South), None],
+            ],
+            layout: MovementTableLayout {
+                ranks: vec![Some(Direction::Down), None],
+                files: vec![Some(Direction::Left), Some(Direction::Left), None],
+            },
+        });
+        initial_state_changes(&mut app);
+
+        // rank: down, file: left
+        assert_eq!(
+            get_icon_button_for_action(&mut app, GraveId::North),
+            &IconButton::AtlasImageIcon(UiAtlasImage {
+                texture_atlas: assets.movement_table_actions.clone(),
+                index: 9
+            })
+        );
+
+        // rank: down, file: none
+        assert_eq!(
+            get_icon_button_for_action(&mut app, GraveId::West),
+            &IconButton::ImageIcon(UiImage::new(assets.down.clone())),
+        );
+
+        // rank: none, file: left
+        assert_eq!(
+            get_icon_button_for_action(&mut app, GraveId::South),
+            &IconButton::ImageIcon(UiImage::new(assets.left.clone())),
+        );
+
+        assert_eq!(
+            get_icon_button_for_action(&mut app, GraveId::East),
+            &IconButton::NoIcon,
+        );
+    }
+
+    #[test]
+    fn grave_id_buttons_show_diagonal_directions() {
+        let mut app = app_setup();
+        let assets = asset_setup(&mut app);
+
+        // 1x2 table with a diagonal rank and a no-op file
+        app.world.spawn(MovementTable {
+            table: vec![vec![Some(GraveId::North), Some(GraveId::West)]],
+            layout: MovementTableLayout {
+                ranks: vec![Some(Direction::UpRight)],
+                files: vec![None, Some(Direction::Left)],
+            },
+        });
+        initial_state_changes(&mut app);
+
+        // rank: up-right, file: none
+        assert_eq!(
+            get_icon_button_for_action(&mut app, GraveId::North),
+            &IconButton::ImageIcon(UiImage::new(assets.up_right.clone())),
+        );
+
+        // rank: up-right, file: left
+        assert_eq!(
+            get_icon_button_for_action(&mut app, GraveId::West),
+            &IconButton::NoIcon,
+        );
+    }
 }
@@ -0,0 +1,132 @@
+//! Plugin providing functionality for conveyors, which move Willo and gravestones after every
+//! grave-action.
+//!
+//! Once Willo's file move is finished, every [SokobanBlock::Dynamic] block standing on a conveyor
+//! is moved one step in the conveyor's direction, pushing anything in its way.
+//! Blocks further along their direction move first, so blocks lined up on a conveyor belt move
+//! together rather than pushing each other twice.
+use crate::{
+    graveyard::willo::{MovementTimer, WilloState},
+    history::FlushHistoryCommands,
+    sokoban::{Direction, SokobanBlock, SokobanCommands, SokobanSets},
+    GameState,
+};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use std::cmp::Reverse;
+
+/// Plugin providing functionality for conveyors, which move Willo and gravestones after every
+/// grave-action.
+pub struct ConveyorPlugin;
+
+/// IntGrid values of the LDtk "IntGrid" layer that are spawned as conveyors, and their directions.
+pub const CONVEYOR_INT_GRID_VALUES: [(i32, Direction); 4] = [
+    (12, Direction::Up),
+    (13, Direction::Left),
+    (14, Direction::Down),
+    (15, Direction::Right),
+];
+
+impl Plugin for ConveyorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            move_by_conveyors
+                .run_if(in_state(GameState::Graveyard))
+                .after(SokobanSets::LogicalMovement)
+                .after(FlushHistoryCommands),
+        );
+
+        CONVEYOR_INT_GRID_VALUES.iter().for_each(|(value, _)| {
+            app.register_ldtk_int_cell::<ConveyorBundle>(*value);
+        });
+    }
+}
+
+/// Component marking conveyor cells and the direction they move blocks in.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Component)]
+pub struct Conveyor(pub Direction);
+
+impl From<IntGridCell> for Conveyor {
+    fn from(int_grid_cell: IntGridCell) -> Self {
+        CONVEYOR_INT_GRID_VALUES
+            .iter()
+            .find(|(value, _)| *value == int_grid_cell.value)
+            .map(|(_, direction)| Conveyor(*direction))
+            .expect("tried to give non-conveyor cell a Conveyor")
+    }
+}
+
+#[derive(Clone, Bundle, LdtkIntCell)]
+struct ConveyorBundle {
+    #[from_int_grid_cell]
+    conveyor: Conveyor,
+}
+
+/// The moves conveyors perform on the given blocks, in the order they are performed in.
+///
+/// Blocks standing on a conveyor, as given by `conveyor_at`, move one step in its direction.
+/// Moves further along their direction come first, and ties are broken by position.
+pub fn conveyor_moves<T>(
+    blocks: impl IntoIterator<Item = (T, GridCoords)>,
+    conveyor_at: impl Fn(GridCoords) -> Option<Direction>,
+) -> Vec<(T, Direction)> {
+    let mut moves: Vec<_> = blocks
+        .into_iter()
+        .filter_map(|(block, grid_coords)| {
+            conveyor_at(grid_coords).map(|direction| (block, grid_coords, direction))
+        })
+        .collect();
+
+    moves.sort_by_key(|(_, grid_coords, direction)| {
+        (
+            Reverse(IVec2::from(*grid_coords).dot(IVec2::from(*direction))),
+            grid_coords.y,
+            grid_coords.x,
+        )
+    });
+
+    moves
+        .into_iter()
+        .map(|(block, _, direction)| (block, direction))
+        .collect()
+}
+
+fn move_by_conveyors(
+    mut willo_query: Query<(&mut MovementTimer, &mut WilloState)>,
+    conveyor_query: Query<(&GridCoords, &Conveyor)>,
+    block_query: Query<(Entity, &GridCoords, &SokobanBlock)>,
+    mut sokoban_commands: SokobanCommands,
+) {
+    let Ok((mut timer, mut willo)) = willo_query.get_single_mut() else {
+        return;
+    };
+
+    if *willo != WilloState::ConveyorMove || !timer.0.finished() {
+        return;
+    }
+
+    let moves = conveyor_moves(
+        block_query
+            .iter()
+            .filter(|(.., sokoban_block)| **sokoban_block == SokobanBlock::Dynamic)
+            .map(|(entity, grid_coords, _)| (entity, *grid_coords)),
+        |grid_coords| {
+            conveyor_query
+                .iter()
+                .find(|(conveyor_grid_coords, _)| **conveyor_grid_coords == grid_coords)
+                .map(|(_, Conveyor(direction))| *direction)
+        },
+    );
+
+    // only wait for the conveyors to finish if they moved something
+    if !moves.is_empty() {
+        timer.0.reset();
+    }
+
+    for (entity, direction) in moves {
+        sokoban_commands.move_block(entity, direction);
+    }
+
+    *willo = WilloState::Waiting;
+}
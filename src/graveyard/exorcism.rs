@@ -24,6 +24,9 @@ pub enum ExorcismSets {
 /// Plugin providing functionality for exorcism tiles, including death logic.
 pub struct ExorcismPlugin;
 
+/// IntGrid value of the LDtk "IntGrid" layer that is spawned as an exorcism tile.
+pub const EXORCISM_INT_GRID_VALUE: i32 = 2;
+
 impl Plugin for ExorcismPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ExorcismEvent>()
@@ -39,7 +42,7 @@ impl Plugin for ExorcismPlugin {
                 ),
             )
             .add_systems(PreUpdate, make_exorcism_card_visible)
-            .register_ldtk_int_cell::<ExorcismTileBundle>(2);
+            .register_ldtk_int_cell::<ExorcismTileBundle>(EXORCISM_INT_GRID_VALUE);
     }
 }
 
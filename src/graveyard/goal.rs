@@ -1,11 +1,21 @@
 //! Plugin providing functionality for goal tiles with victory logic and goal ghost visuals.
+//!
+//! A level is won once every goal is met by a gravestone.
+//! Levels that also contain exit gates additionally require Willo to finish a grave-action
+//! standing on one, and the gates only open once every goal is met.
 use crate::{
-    graveyard::{exorcism::ExorcismSets, gravestone::GraveId},
+    graveyard::{
+        exorcism::ExorcismSets,
+        gravestone::{key_icon_bundle, GraveId, GravestoneAssets},
+        move_counter::{level_par, LevelScore, MoveCount},
+        willo::{MovementTimer, WilloState},
+    },
     level_transition::TransitionTo,
     AssetHolder, GameState,
 };
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
+use leafwing_input_manager::prelude::*;
 use rand::Rng;
 use std::{ops::Range, time::Duration};
 
@@ -25,19 +35,95 @@ impl Plugin for GoalPlugin {
                         .after(ExorcismSets::CheckDeath),
                     goal_ghost_animation.run_if(not(in_state(GameState::AssetLoading))),
                     goal_ghost_event_sugar.run_if(not(in_state(GameState::AssetLoading))),
+                    exit_gate_event_sugar.run_if(not(in_state(GameState::AssetLoading))),
                 ),
             )
-            .register_ldtk_entity::<GoalBundle>("Goal");
+            .register_ldtk_entity::<GoalBundle>("Goal")
+            .register_ldtk_entity::<ExitGateBundle>("Exit");
     }
 }
 
 /// Component that marks goal tiles and stores whether or not it is currently "satisfied".
+///
+/// Goals with a `grave_id` can only be satisfied by that particular gravestone.
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Component)]
 struct Goal {
     met: bool,
+    grave_id: Option<GraveId>,
+}
+
+/// The first of the given gravestones that meets the goal at the given position.
+///
+/// Goals restricted to a `goal_grave_id` can only be met by that particular gravestone.
+pub fn meeting_gravestone<T>(
+    goal_grid_coords: GridCoords,
+    goal_grave_id: Option<GraveId>,
+    gravestones: impl IntoIterator<Item = (T, GridCoords, GraveId)>,
+) -> Option<T> {
+    gravestones
+        .into_iter()
+        .find(|(_, grid_coords, grave_id)| {
+            *grid_coords == goal_grid_coords
+                && goal_grave_id.is_none_or(|goal_grave_id| goal_grave_id == *grave_id)
+        })
+        .map(|(gravestone, ..)| gravestone)
+}
+
+/// Returns `true` if a level with the given goals and exit gates is complete.
+///
+/// Every goal must be met, and if there are exit gates, Willo must have finished their
+/// grave-action on one of them.
+/// `willo_finished_at` is Willo's position once their grave-action is finished, or `None` while
+/// they are still moving or dead.
+///
+/// Levels without goals or exit gates are never complete, so levels that haven't been loaded (for
+/// example when hot-reloading levels) don't skip to the next one.
+pub fn level_complete(
+    goals_met: impl IntoIterator<Item = bool>,
+    exits: impl IntoIterator<Item = GridCoords>,
+    willo_finished_at: Option<GridCoords>,
+) -> bool {
+    let mut goals_met = goals_met.into_iter().peekable();
+    let mut exits = exits.into_iter().peekable();
+
+    if goals_met.peek().is_none() && exits.peek().is_none() {
+        return false;
+    }
+
+    goals_met.all(|met| met)
+        && (exits.peek().is_none()
+            || willo_finished_at.is_some_and(|willo| exits.any(|exit| exit == willo)))
+}
+
+impl From<&EntityInstance> for Goal {
+    fn from(entity_instance: &EntityInstance) -> Goal {
+        Goal {
+            met: false,
+            grave_id: goal_grave_id(entity_instance),
+        }
+    }
 }
 
-/// Event that fires when a goal's state changes.
+/// The gravestone a goal entity is restricted to, as defined by its optional "Grave" LDtk field.
+///
+/// The field should contain the gravestone's LDtk identifier, like "Q" or "W".
+pub fn goal_grave_id(entity_instance: &EntityInstance) -> Option<GraveId> {
+    entity_instance
+        .get_maybe_string_field("Grave")
+        .ok()
+        .and_then(|grave| grave.as_deref())
+        .and_then(GraveId::from_ldtk_identifier)
+}
+
+/// Component that marks exit gates and stores whether or not they are currently open.
+///
+/// Gates are open while every goal in the level is met.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Component)]
+struct ExitGate {
+    open: bool,
+}
+
+/// Event that fires when a goal's or exit gate's state changes.
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Event)]
 enum GoalEvent {
     Met {
@@ -47,19 +133,38 @@ enum GoalEvent {
     UnMet {
         goal_entity: Entity,
     },
+    ExitOpened {
+        exit_entity: Entity,
+    },
+    ExitClosed {
+        exit_entity: Entity,
+    },
 }
 
 #[derive(Clone, Default, Bundle, LdtkEntity)]
 struct GoalBundle {
     #[grid_coords]
     grid_coords: GridCoords,
+    #[from_entity_instance]
     goal: Goal,
     #[sprite_sheet_bundle]
     sprite_sheet_bundle: SpriteSheetBundle,
 }
 
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+struct ExitGateBundle {
+    #[grid_coords]
+    grid_coords: GridCoords,
+    exit_gate: ExitGate,
+    #[sprite_sheet_bundle]
+    sprite_sheet_bundle: SpriteSheetBundle,
+}
+
+/// Sprite color of closed exit gates.
+const CLOSED_EXIT_GATE_COLOR: Color = Color::rgba(0.5, 0.5, 0.5, 0.5);
+
 /// Resource for defining the visual behavior of goal ghosts.
-#[derive(Clone, Eq, PartialEq, Debug, Hash, Resource)]
+#[derive(Clone, PartialEq, Debug, Resource)]
 struct GoalGhostSettings {
     no_turn_length: Range<usize>,
     turn_length: Range<usize>,
@@ -71,6 +176,7 @@ struct GoalGhostSettings {
     none_frame_index: usize,
     num_columns: usize,
     num_rows: usize,
+    icon_translation: Vec3,
     atlas: Option<Handle<TextureAtlas>>,
 }
 
@@ -87,6 +193,7 @@ impl Default for GoalGhostSettings {
             none_frame_index: 8,
             num_columns: 10,
             num_rows: 5,
+            icon_translation: Vec3::new(0., 14., 0.1),
             atlas: None,
         }
     }
@@ -153,50 +260,75 @@ fn range_chance(range: &Range<usize>, current: usize) -> f32 {
 fn check_goal(
     mut commands: Commands,
     mut goal_query: Query<(Entity, &mut Goal, &GridCoords), With<Goal>>,
-    block_query: Query<(Entity, &GridCoords), With<GraveId>>,
+    block_query: Query<(Entity, &GridCoords, &GraveId)>,
+    mut exit_query: Query<(Entity, &mut ExitGate, &GridCoords)>,
+    willo_query: Query<(&WilloState, &MovementTimer, &GridCoords, &MoveCount)>,
     mut goal_events: EventWriter<GoalEvent>,
     mut next_state: ResMut<NextState<GameState>>,
     level_selection: Res<LevelSelection>,
     ldtk_assets: Res<Assets<LdtkProject>>,
     asset_holder: Res<AssetHolder>,
 ) {
-    // If the goal is not loaded for whatever reason (for example when hot-reloading levels),
-    // the goal will automatically be "met", loading the next level.
-    // This if statement prevents that.
-    if goal_query.iter().count() == 0 {
-        return;
-    }
-
-    let mut level_goal_met = true;
+    let mut goals_met = Vec::new();
 
     for (goal_entity, mut goal, goal_grid_coords) in goal_query.iter_mut() {
-        let mut goal_met = false;
-        for (stone_entity, block_grid_coords) in block_query.iter() {
-            if goal_grid_coords == block_grid_coords {
-                goal_met = true;
-
-                if !goal.met {
-                    goal.met = true;
-
-                    goal_events.send(GoalEvent::Met {
-                        stone_entity,
-                        goal_entity,
-                    });
-                }
-
-                break;
+        let stone_entity = meeting_gravestone(
+            *goal_grid_coords,
+            goal.grave_id,
+            block_query
+                .iter()
+                .map(|(entity, grid_coords, grave_id)| (entity, *grid_coords, *grave_id)),
+        );
+
+        match stone_entity {
+            Some(stone_entity) if !goal.met => {
+                goal.met = true;
+
+                goal_events.send(GoalEvent::Met {
+                    stone_entity,
+                    goal_entity,
+                });
             }
-        }
-        if !goal_met {
-            level_goal_met = false;
-
-            if goal.met {
+            None if goal.met => {
                 goal_events.send(GoalEvent::UnMet { goal_entity });
                 goal.met = false;
             }
+            _ => (),
         }
+
+        goals_met.push(goal.met);
     }
 
+    let every_goal_met = goals_met.iter().all(|met| *met);
+
+    for (exit_entity, mut exit_gate, _) in exit_query.iter_mut() {
+        if exit_gate.open != every_goal_met {
+            exit_gate.open = every_goal_met;
+
+            goal_events.send(if every_goal_met {
+                GoalEvent::ExitOpened { exit_entity }
+            } else {
+                GoalEvent::ExitClosed { exit_entity }
+            });
+        }
+    }
+
+    // Willo's grave-action is only finished once the conveyors have moved them too.
+    let willo_finished_at =
+        willo_query
+            .get_single()
+            .ok()
+            .and_then(|(willo, movement_timer, willo_grid_coords, _)| {
+                (*willo == WilloState::Waiting && movement_timer.0.finished())
+                    .then_some(*willo_grid_coords)
+            });
+
+    let level_goal_met = level_complete(
+        goals_met,
+        exit_query.iter().map(|(.., grid_coords)| *grid_coords),
+        willo_finished_at,
+    );
+
     if level_goal_met {
         next_state.set(GameState::LevelTransition);
 
@@ -213,6 +345,13 @@ fn check_goal(
             // Currently this doesn't have a time buffer like it used to.
             // This will change as we make a more elaborate level transition workflow.
             commands.insert_resource(TransitionTo(LevelSelection::index(level_index + 1)));
+
+            commands.insert_resource(LevelScore {
+                moves: willo_query
+                    .get_single()
+                    .map_or(0, |(.., move_count)| move_count.0),
+                par: level_par(selected_level),
+            });
         }
 
         commands.spawn(AudioBundle {
@@ -238,6 +377,7 @@ fn goal_ghost_event_sugar(
                         animation.state = GoalAnimationState::Idle;
                     }
                 }
+                GoalEvent::ExitOpened { .. } | GoalEvent::ExitClosed { .. } => (),
             }
         }
     }
@@ -349,14 +489,41 @@ fn goal_ghost_animation(
     }
 }
 
+fn exit_gate_event_sugar(
+    mut exit_query: Query<&mut TextureAtlasSprite, With<ExitGate>>,
+    added_exits: Query<Entity, Added<ExitGate>>,
+    mut goal_events: EventReader<GoalEvent>,
+) {
+    // gates start closed
+    for exit_entity in added_exits.iter() {
+        if let Ok(mut sprite) = exit_query.get_mut(exit_entity) {
+            sprite.color = CLOSED_EXIT_GATE_COLOR;
+        }
+    }
+
+    for event in goal_events.read() {
+        let (exit_entity, color) = match event {
+            GoalEvent::ExitOpened { exit_entity } => (exit_entity, Color::WHITE),
+            GoalEvent::ExitClosed { exit_entity } => (exit_entity, CLOSED_EXIT_GATE_COLOR),
+            _ => continue,
+        };
+
+        if let Ok(mut sprite) = exit_query.get_mut(*exit_entity) {
+            sprite.color = color;
+        }
+    }
+}
+
 fn spawn_goal_ghosts(
     mut commands: Commands,
-    goals: Query<Entity, Added<Goal>>,
+    goals: Query<(Entity, &Goal), Added<Goal>>,
     mut goal_ghost_settings: ResMut<GoalGhostSettings>,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    gravestone_assets: Res<GravestoneAssets>,
+    input_map: Res<InputMap<GraveId>>,
 ) {
-    for goal_entity in goals.iter() {
+    for (goal_entity, goal) in goals.iter() {
         let atlas_handle = match &goal_ghost_settings.atlas {
             Some(atlas) => atlas.clone(),
             None => {
@@ -386,6 +553,19 @@ fn spawn_goal_ghosts(
                 goal_entity,
                 Timer::new(goal_ghost_settings.frame_duration, TimerMode::Repeating),
             ))
+            .with_children(|parent| {
+                // restricted goals show the key of the gravestone they're waiting for
+                if let Some(icon) = goal.grave_id.and_then(|grave_id| {
+                    key_icon_bundle(
+                        grave_id,
+                        &gravestone_assets,
+                        &input_map,
+                        goal_ghost_settings.icon_translation,
+                    )
+                }) {
+                    parent.spawn(icon);
+                }
+            })
             .id();
 
         commands.entity(goal_entity).add_child(ghost_entity);
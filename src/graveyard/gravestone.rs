@@ -144,17 +144,71 @@ fn load_gravestone_control_settings(asset_folder: String) -> std::io::Result<Inp
     }
 }
 
+impl GraveId {
+    /// All grave-actions, in the order they appear on the control display.
+    pub const ALL: [GraveId; 6] = [
+        GraveId::Northwest,
+        GraveId::North,
+        GraveId::Northeast,
+        GraveId::West,
+        GraveId::South,
+        GraveId::East,
+    ];
+
+    /// The identifier of the LDtk entity for this gravestone, which doubles as its default key.
+    pub fn ldtk_identifier(&self) -> &'static str {
+        match self {
+            GraveId::Northwest => "Q",
+            GraveId::North => "W",
+            GraveId::Northeast => "E",
+            GraveId::West => "A",
+            GraveId::South => "S",
+            GraveId::East => "D",
+        }
+    }
+
+    /// The grave-action whose gravestone has the given LDtk identifier, if any.
+    pub fn from_ldtk_identifier(identifier: &str) -> Option<GraveId> {
+        GraveId::ALL
+            .into_iter()
+            .find(|grave_id| grave_id.ldtk_identifier() == identifier)
+    }
+}
+
 impl From<&EntityInstance> for GraveId {
     fn from(entity_instance: &EntityInstance) -> Self {
-        match entity_instance.identifier.as_ref() {
-            "Q" => GraveId::Northwest,
-            "W" => GraveId::North,
-            "E" => GraveId::Northeast,
-            "A" => GraveId::West,
-            "S" => GraveId::South,
-            "D" => GraveId::East,
-            g => panic!("encountered bad gravestone identifier: {g}"),
-        }
+        GraveId::from_ldtk_identifier(&entity_instance.identifier).unwrap_or_else(|| {
+            panic!(
+                "encountered bad gravestone identifier: {}",
+                entity_instance.identifier
+            )
+        })
+    }
+}
+
+/// Sprite displaying the keyboard key bound to the grave-action, if it has one.
+pub fn key_icon_bundle(
+    grave_id: GraveId,
+    assets: &GravestoneAssets,
+    input_map: &InputMap<GraveId>,
+    translation: Vec3,
+) -> Option<SpriteSheetBundle> {
+    match input_map
+        .get(grave_id)
+        .iter()
+        .flat_map(|inputs| inputs.iter())
+        .find(|i| matches!(i, UserInput::Single(InputKind::Keyboard(_))))
+    {
+        Some(UserInput::Single(InputKind::Keyboard(key_code))) => Some(SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                index: key_code.variant_index(),
+                ..default()
+            },
+            texture_atlas: assets.key_code_icons.clone(),
+            transform: Transform::from_translation(translation),
+            ..default()
+        }),
+        _ => None,
     }
 }
 
@@ -202,21 +256,10 @@ fn spawn_gravestone_body(
             });
 
             // icon entity
-            if let Some(UserInput::Single(InputKind::Keyboard(key_code))) = input_map
-                .get(*grave_id)
-                .iter()
-                .flat_map(|inputs| inputs.iter())
-                .find(|i| matches!(i, UserInput::Single(InputKind::Keyboard(_))))
+            if let Some(icon) =
+                key_icon_bundle(*grave_id, &assets, &input_map, settings.icon_translation)
             {
-                parent.spawn(SpriteSheetBundle {
-                    sprite: TextureAtlasSprite {
-                        index: key_code.variant_index(),
-                        ..default()
-                    },
-                    texture_atlas: assets.key_code_icons.clone(),
-                    transform: Transform::from_translation(settings.icon_translation),
-                    ..default()
-                });
+                parent.spawn(icon);
             }
         });
     }
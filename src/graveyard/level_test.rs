@@ -0,0 +1,574 @@
+//! Headless test harness for playing the shipped levels through the graveyard plugins.
+//!
+//! [LevelTest] loads a level from the real LDtk project, or a small [TestLevel] exercising a single
+//! mechanic, and spawns its entities using the bundles registered by [GraveyardPlugin].
+//! Scripted inputs are then fed through the same pipeline as replays, see [ReplayPlayback], or
+//! pressed the way a player would, so they are recorded to the [ReplayRecording].
+//!
+//! The LDtk asset pipeline is skipped entirely, so no window, GPU, or asset loading is required.
+use crate::{
+    animation::SpriteSheetAnimationPlugin,
+    graveyard::{
+        control_display::ControlDisplayAssets,
+        gravestone::{GraveId, GravestoneAssets},
+        level_push_rule,
+        replay::{press_input, Replay, ReplayInput, ReplayPlayback, ReplayRecording},
+        wall::WALL_INT_GRID_VALUES,
+        willo::{MovementTimer, WilloState},
+        GraveyardAction, GraveyardPlugin, SOKOBAN_LAYER_IDENTIFIER,
+    },
+    AssetHolder, GameState, UNIT_LENGTH,
+};
+use bevy::{ecs::system::SystemState, prelude::*, time::TimeUpdateStrategy};
+use bevy_ecs_ldtk::{
+    app::{LdtkEntityMap, LdtkIntCellMap},
+    ldtk::{LayerInstance, LdtkJson, Level},
+    prelude::*,
+    utils::{grid_coords_to_ldtk_grid_coords, ldtk_grid_coords_to_grid_coords},
+};
+use leafwing_input_manager::{plugin::InputManagerSystem, prelude::*};
+use std::{collections::VecDeque, fs::File, io::BufReader, path::Path, time::Duration};
+
+/// Path to the LDtk project, relative to the crate root.
+const PROJECT_PATH: &str = "assets/levels/willos-graveyard.ldtk";
+
+/// Identifiers of the LDtk entities that are spawned, other entities are purely cosmetic.
+const SPAWNED_ENTITIES: &[&str] = &[
+    "Willo",
+    "Table",
+    "Goal",
+    "Exit",
+    "Teleporter",
+    "Coffin",
+    "Q",
+    "W",
+    "E",
+    "A",
+    "S",
+    "D",
+];
+
+/// Simulated duration of every frame.
+const FRAME_DURATION: Duration = Duration::from_millis(20);
+
+/// Maximum number of frames a script may take to play out.
+const MAX_FRAMES: usize = 20_000;
+
+fn load_json<T: serde::de::DeserializeOwned>(path: &Path) -> T {
+    serde_json::from_reader(BufReader::new(
+        File::open(path).unwrap_or_else(|e| panic!("unable to open {}: {e}", path.display())),
+    ))
+    .unwrap_or_else(|e| panic!("unable to parse {}: {e}", path.display()))
+}
+
+/// Loads a level from the LDtk project, including its external level file.
+fn load_level(level_identifier: &str) -> Level {
+    let project_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(PROJECT_PATH);
+    let project: LdtkJson = load_json(&project_path);
+
+    let level = project
+        .levels
+        .into_iter()
+        .find(|level| level.identifier == level_identifier)
+        .unwrap_or_else(|| panic!("level {level_identifier} should exist in project"));
+
+    match &level.external_rel_path {
+        Some(external_rel_path) => load_json(
+            &project_path
+                .parent()
+                .expect("project path should have a parent directory")
+                .join(external_rel_path),
+        ),
+        None => level,
+    }
+}
+
+/// Spawns the level's gameplay entities using the bundles registered with `bevy_ecs_ldtk`.
+fn spawn_level(world: &mut World, level: &Level) {
+    let mut system_state: SystemState<(
+        Commands,
+        NonSend<LdtkEntityMap>,
+        NonSend<LdtkIntCellMap>,
+        Res<AssetServer>,
+        ResMut<Assets<TextureAtlas>>,
+    )> = SystemState::new(world);
+
+    let (mut commands, entity_map, int_cell_map, asset_server, mut texture_atlases) =
+        system_state.get_mut(world);
+
+    for layer in level
+        .layer_instances
+        .as_ref()
+        .expect("level should have layer instances")
+    {
+        if layer.identifier == SOKOBAN_LAYER_IDENTIFIER {
+            commands.spawn(LayerMetadata::from(layer));
+        }
+
+        for entity_instance in layer
+            .entity_instances
+            .iter()
+            .filter(|entity_instance| SPAWNED_ENTITIES.contains(&&*entity_instance.identifier))
+        {
+            entity_map
+                .get(&(None, Some(entity_instance.identifier.clone())))
+                .unwrap_or_else(|| panic!("{} should be registered", entity_instance.identifier))
+                .evaluate(
+                    // bevy_ecs_ldtk spawns entities with a SpatialBundle before evaluating them
+                    &mut commands.spawn(SpatialBundle::default()),
+                    entity_instance,
+                    layer,
+                    None,
+                    None,
+                    &asset_server,
+                    &mut texture_atlases,
+                );
+        }
+
+        for (i, value) in layer.int_grid_csv.iter().enumerate() {
+            if let Some(int_cell) = int_cell_map.get(&(None, Some(*value))) {
+                let ldtk_coords = IVec2::new(i as i32 % layer.c_wid, i as i32 / layer.c_wid);
+                let int_grid_cell = IntGridCell { value: *value };
+
+                int_cell.evaluate(
+                    &mut commands.spawn((
+                        ldtk_grid_coords_to_grid_coords(ldtk_coords, layer.c_hei),
+                        int_grid_cell,
+                    )),
+                    int_grid_cell,
+                    layer,
+                );
+            }
+        }
+    }
+
+    system_state.apply(world);
+}
+
+/// Builder for small levels exercising a single mechanic.
+///
+/// Levels are 10x6, with a 5x6 play area on the left and the movement table on the right, like the
+/// simulation's test graveyard.
+/// The table's rank `r` and file `f` is at `(6 + f, 4 - r)`, and the play area is walled off from
+/// the table.
+pub struct TestLevel {
+    level: Level,
+}
+
+impl TestLevel {
+    const SIZE: IVec2 = IVec2::new(10, 6);
+
+    /// Constructs a level with Willo at the given position, and nothing in the play area.
+    pub fn new(identifier: &str, willo: GridCoords) -> TestLevel {
+        let layer = |identifier: &str| LayerInstance {
+            identifier: identifier.to_string(),
+            c_wid: TestLevel::SIZE.x,
+            c_hei: TestLevel::SIZE.y,
+            grid_size: UNIT_LENGTH,
+            int_grid_csv: vec![0; (TestLevel::SIZE.x * TestLevel::SIZE.y) as usize],
+            ..default()
+        };
+
+        let mut test_level = TestLevel {
+            level: Level {
+                identifier: identifier.to_string(),
+                px_wid: TestLevel::SIZE.x * UNIT_LENGTH,
+                px_hei: TestLevel::SIZE.y * UNIT_LENGTH,
+                layer_instances: Some(vec![layer("Entities"), layer(SOKOBAN_LAYER_IDENTIFIER)]),
+                ..default()
+            },
+        }
+        .entity("Willo", willo)
+        .entity("Table", GridCoords::new(5, 5));
+
+        for y in 0..TestLevel::SIZE.y {
+            test_level = test_level.int_cell(GridCoords::new(5, y), WALL_INT_GRID_VALUES[0]);
+        }
+
+        test_level
+    }
+
+    fn layer_mut(&mut self, identifier: &str) -> &mut LayerInstance {
+        self.level
+            .layer_instances
+            .as_mut()
+            .and_then(|layers| {
+                layers
+                    .iter_mut()
+                    .find(|layer| layer.identifier == identifier)
+            })
+            .expect("test level should have the layer")
+    }
+
+    /// Sets the IntGrid value of the cell at the given position.
+    pub fn int_cell(mut self, grid_coords: GridCoords, value: i32) -> TestLevel {
+        let ldtk_coords = grid_coords_to_ldtk_grid_coords(grid_coords, TestLevel::SIZE.y);
+        let index = (ldtk_coords.y * TestLevel::SIZE.x + ldtk_coords.x) as usize;
+
+        self.layer_mut(SOKOBAN_LAYER_IDENTIFIER).int_grid_csv[index] = value;
+        self
+    }
+
+    /// Adds a single-cell entity at the given position.
+    pub fn entity(mut self, identifier: &str, grid_coords: GridCoords) -> TestLevel {
+        let grid = grid_coords_to_ldtk_grid_coords(grid_coords, TestLevel::SIZE.y);
+
+        self.layer_mut("Entities")
+            .entity_instances
+            .push(EntityInstance {
+                identifier: identifier.to_string(),
+                grid,
+                px: grid * UNIT_LENGTH,
+                width: UNIT_LENGTH,
+                height: UNIT_LENGTH,
+                ..default()
+            });
+        self
+    }
+
+    /// Adds a gravestone for the given grave-action at the given position.
+    pub fn gravestone(self, grave_id: GraveId, grid_coords: GridCoords) -> TestLevel {
+        self.entity(grave_id.ldtk_identifier(), grid_coords)
+    }
+
+    /// The level that has been built.
+    pub fn build(self) -> Level {
+        self.level
+    }
+}
+
+/// Resource storing inputs to press as soon as Willo is idle, like a player would.
+#[derive(Debug, Default, Resource)]
+struct PlayerInputs(VecDeque<ReplayInput>);
+
+fn press_player_inputs(
+    mut commands: Commands,
+    mut player_inputs: ResMut<PlayerInputs>,
+    willo_query: Query<(&WilloState, &MovementTimer)>,
+    mut grave_input: ResMut<ActionState<GraveId>>,
+    mut graveyard_input: ResMut<ActionState<GraveyardAction>>,
+) {
+    let Ok((willo, movement_timer)) = willo_query.get_single() else {
+        return;
+    };
+
+    if !matches!(willo, WilloState::Waiting | WilloState::Dead) || !movement_timer.0.finished() {
+        return;
+    }
+
+    match player_inputs.0.pop_front() {
+        Some(input) => press_input(input, &mut grave_input, &mut graveyard_input),
+        None => commands.remove_resource::<PlayerInputs>(),
+    }
+}
+
+/// Headless app playing a single level from the LDtk project.
+///
+/// See the [module-level docs](self) for more info.
+pub struct LevelTest {
+    level_identifier: String,
+    app: App,
+}
+
+impl LevelTest {
+    /// Loads and spawns the level with the given identifier, entering the graveyard state.
+    pub fn new(level_identifier: &str) -> LevelTest {
+        LevelTest::from_level(&load_level(level_identifier))
+    }
+
+    /// Spawns the given level, entering the graveyard state.
+    pub fn from_level(level: &Level) -> LevelTest {
+        let level_identifier = level.identifier.as_str();
+        let mut app = App::new();
+
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            bevy::input::InputPlugin,
+        ))
+        .init_asset::<Image>()
+        .init_asset::<TextureAtlas>()
+        .init_asset::<Font>()
+        .init_asset::<LdtkProject>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME_DURATION))
+        .add_state::<GameState>()
+        .init_resource::<AssetHolder>()
+        .init_resource::<GravestoneAssets>()
+        .init_resource::<ControlDisplayAssets>()
+        .insert_resource(LevelSelection::Identifier(level_identifier.to_string()))
+        .add_plugins((SpriteSheetAnimationPlugin, GraveyardPlugin))
+        .add_systems(
+            PreUpdate,
+            press_player_inputs
+                .run_if(resource_exists::<PlayerInputs>())
+                .in_set(InputManagerSystem::ManualControl),
+        )
+        // levels are entered through the level transition in-game, which starts the recording and
+        // sets the push rule
+        .insert_resource(ReplayRecording::new(level_identifier))
+        .insert_resource(level_push_rule(level));
+
+        spawn_level(&mut app.world, level);
+
+        app.world
+            .insert_resource(NextState(Some(GameState::Graveyard)));
+        app.update();
+
+        LevelTest {
+            level_identifier: level_identifier.to_string(),
+            app,
+        }
+    }
+
+    /// Performs the inputs in order, waiting for Willo to finish moving after each of them.
+    ///
+    /// The inputs are played back as a replay, so they aren't recorded.
+    /// Stops early if the level is completed.
+    pub fn perform(&mut self, inputs: impl IntoIterator<Item = ReplayInput>) -> &mut LevelTest {
+        self.app.insert_resource(ReplayPlayback::new(Replay {
+            level: self.level_identifier.clone(),
+            inputs: inputs.into_iter().collect(),
+        }));
+
+        self.run_until_idle::<ReplayPlayback>()
+    }
+
+    /// Presses the inputs in order as a player would, so that they are recorded.
+    ///
+    /// Stops early if the level is completed.
+    pub fn press(&mut self, inputs: impl IntoIterator<Item = ReplayInput>) -> &mut LevelTest {
+        self.app
+            .insert_resource(PlayerInputs(inputs.into_iter().collect()));
+
+        self.run_until_idle::<PlayerInputs>()
+    }
+
+    /// Runs the app until the level is completed, or until the inputs resource `R` is removed and
+    /// Willo is idle.
+    fn run_until_idle<R: Resource>(&mut self) -> &mut LevelTest {
+        for _ in 0..MAX_FRAMES {
+            let willo_idle = matches!(self.willo_state(), WilloState::Waiting | WilloState::Dead);
+
+            if self.is_complete() || (willo_idle && !self.app.world.contains_resource::<R>()) {
+                return self;
+            }
+
+            self.app.update();
+        }
+
+        panic!("inputs did not finish playing within {MAX_FRAMES} frames");
+    }
+
+    /// The inputs recorded in this level so far.
+    pub fn recording(&self) -> Option<&Replay> {
+        self.app.world.resource::<ReplayRecording>().as_ref()
+    }
+
+    /// Performs the grave-actions in order, see [LevelTest::perform].
+    pub fn grave_actions(
+        &mut self,
+        grave_ids: impl IntoIterator<Item = GraveId>,
+    ) -> &mut LevelTest {
+        self.perform(grave_ids.into_iter().map(ReplayInput::GraveAction))
+    }
+
+    /// Returns `true` if every goal has been met, triggering the transition to the next level.
+    pub fn is_complete(&self) -> bool {
+        self.app.world.resource::<NextState<GameState>>().0 == Some(GameState::LevelTransition)
+    }
+
+    /// Willo's position.
+    pub fn willo_coords(&mut self) -> GridCoords {
+        *self
+            .app
+            .world
+            .query_filtered::<&GridCoords, With<WilloState>>()
+            .single(&self.app.world)
+    }
+
+    /// The position of the gravestone for the given grave-action.
+    pub fn gravestone_coords(&mut self, grave_id: GraveId) -> GridCoords {
+        self.app
+            .world
+            .query::<(&GridCoords, &GraveId)>()
+            .iter(&self.app.world)
+            .find(|(_, other)| **other == grave_id)
+            .map(|(grid_coords, _)| *grid_coords)
+            .unwrap_or_else(|| panic!("level should have a {grave_id:?} gravestone"))
+    }
+
+    /// Returns `true` if Willo has been exorcised.
+    pub fn is_willo_dead(&mut self) -> bool {
+        self.willo_state() == WilloState::Dead
+    }
+
+    fn willo_state(&mut self) -> WilloState {
+        *self
+            .app
+            .world
+            .query::<&WilloState>()
+            .single(&self.app.world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graveyard::{
+            conveyor::CONVEYOR_INT_GRID_VALUES,
+            pit::{Pit, PIT_INT_GRID_VALUE},
+            volatile::Volatile,
+        },
+        sokoban::Direction,
+    };
+
+    fn grave_ids(keys: &str) -> Vec<GraveId> {
+        keys.chars()
+            .map(|c| match ReplayInput::from_char(c) {
+                Some(ReplayInput::GraveAction(grave_id)) => grave_id,
+                _ => panic!("{c} is not a grave-action"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn first_level_completes() {
+        let mut level = LevelTest::new("Some_poor_soul_lost_their_gravestone");
+
+        level.grave_actions(grave_ids("ASWWDSDSASWASWDDAADSDWW"));
+
+        assert!(level.is_complete());
+        assert!(!level.is_willo_dead());
+    }
+
+    #[test]
+    fn incomplete_solution_does_not_complete() {
+        let mut level = LevelTest::new("Some_poor_soul_lost_their_gravestone");
+
+        level.grave_actions(grave_ids("ASWWDSDSASWASWDDAADSDW"));
+
+        assert!(!level.is_complete());
+    }
+
+    #[test]
+    fn recorded_inputs_play_back() {
+        let mut level = LevelTest::new("Some_poor_soul_lost_their_gravestone");
+
+        let inputs: Vec<_> = "ASWWDZDSDSASWASWDDAADSDWW"
+            .chars()
+            .map(|c| ReplayInput::from_char(c).unwrap())
+            .collect();
+
+        level.press(inputs.clone());
+
+        assert!(level.is_complete());
+
+        let replay = level.recording().expect("level should be recorded").clone();
+
+        assert_eq!(replay.level, "Some_poor_soul_lost_their_gravestone");
+        assert_eq!(replay.inputs, inputs);
+
+        let mut playback = LevelTest::new(&replay.level);
+        playback.perform(replay.inputs);
+
+        assert!(playback.is_complete());
+    }
+
+    #[test]
+    fn exits_are_checked_after_the_conveyor_move() {
+        let (conveyor_value, _) = CONVEYOR_INT_GRID_VALUES
+            .into_iter()
+            .find(|(_, direction)| *direction == Direction::Up)
+            .expect("there should be an upward conveyor");
+
+        // Willo finishes the file move on the exit, but the conveyor carries them off of it
+        let mut level = LevelTest::from_level(
+            &TestLevel::new("Exit_conveyor", GridCoords::new(0, 2))
+                // rank: right, file: right
+                .gravestone(GraveId::North, GridCoords::new(9, 1))
+                .gravestone(GraveId::West, GridCoords::new(1, 2))
+                .entity("Goal", GridCoords::new(3, 2))
+                .entity("Exit", GridCoords::new(2, 2))
+                .int_cell(GridCoords::new(2, 2), conveyor_value)
+                .build(),
+        );
+
+        level.grave_actions([GraveId::North]);
+
+        assert_eq!(level.willo_coords(), GridCoords::new(2, 3));
+        assert!(!level.is_complete());
+    }
+
+    /// The state of the only pit, and of the West gravestone.
+    fn pit_and_gravestone(level: &mut LevelTest) -> (Pit, Volatile) {
+        let world = &mut level.app.world;
+        let pit = *world.query::<&Pit>().single(world);
+        let (_, volatile) = world
+            .query::<(&GraveId, &Volatile)>()
+            .iter(world)
+            .find(|(grave_id, _)| **grave_id == GraveId::West)
+            .expect("level should have a West gravestone");
+
+        (pit, *volatile)
+    }
+
+    #[test]
+    fn filled_pits_open_again_on_undo() {
+        let mut level = LevelTest::from_level(
+            &TestLevel::new("Pit", GridCoords::new(0, 2))
+                // rank: right, file: right
+                .gravestone(GraveId::North, GridCoords::new(9, 1))
+                .gravestone(GraveId::West, GridCoords::new(2, 2))
+                .int_cell(GridCoords::new(3, 2), PIT_INT_GRID_VALUE)
+                .build(),
+        );
+
+        // Willo pushes the gravestone into the pit, then walks over the filled pit
+        level.grave_actions([GraveId::North, GraveId::North]);
+
+        assert_eq!(level.willo_coords(), GridCoords::new(4, 2));
+        assert_eq!(
+            pit_and_gravestone(&mut level),
+            (Pit::Filled, Volatile::Sublimated)
+        );
+
+        level.perform([ReplayInput::Undo, ReplayInput::Undo]);
+
+        assert_eq!(level.willo_coords(), GridCoords::new(0, 2));
+        assert_eq!(
+            level.gravestone_coords(GraveId::West),
+            GridCoords::new(2, 2)
+        );
+        assert_eq!(pit_and_gravestone(&mut level), (Pit::Open, Volatile::Solid));
+
+        // the pit swallows the gravestone again, and blocks Willo until it does
+        level.grave_actions([GraveId::North]);
+
+        assert_eq!(level.willo_coords(), GridCoords::new(2, 2));
+        assert_eq!(
+            pit_and_gravestone(&mut level),
+            (Pit::Filled, Volatile::Sublimated)
+        );
+    }
+
+    #[test]
+    fn exorcism_kills_willo_until_restart() {
+        let mut level = LevelTest::new("Trocar");
+
+        level.grave_actions(grave_ids("DD"));
+
+        assert!(level.is_willo_dead());
+        assert!(!level.is_complete());
+
+        level.perform([ReplayInput::Undo]);
+
+        assert!(!level.is_willo_dead());
+
+        level
+            .perform([ReplayInput::Restart])
+            .grave_actions(grave_ids("WWDDSAASAWSSDWDWWASWWASSDDD"));
+
+        assert!(level.is_complete());
+    }
+}
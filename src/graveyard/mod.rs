@@ -2,11 +2,22 @@
 //!
 //! So, the logic for core gameplay lives here.
 
+pub mod coffin;
 pub mod control_display;
+pub mod conveyor;
 pub mod exorcism;
 pub mod goal;
 pub mod gravestone;
+#[cfg(test)]
+mod level_test;
+pub mod move_counter;
 pub mod movement_table;
+pub mod path_preview;
+pub mod pit;
+pub mod replay;
+pub mod simulation;
+pub mod solver;
+pub mod teleporter;
 pub mod volatile;
 pub mod wall;
 pub mod willo;
@@ -14,15 +25,22 @@ pub mod wind;
 
 use crate::{
     history::{FlushHistoryCommands, HistoryCommands},
-    sokoban,
+    sokoban::{self, PushRule},
     ui::{action::UiActionPlugin, button_prompt::ButtonPromptPlugin},
-    GameState,
+    AssetHolder, GameState,
 };
 use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::Level, prelude::*};
 use leafwing_input_manager::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{fs::File, io::BufReader, ops::Range, time::Duration};
 
+/// Identifier of the LDtk layer that is treated as the sokoban grid.
+pub const SOKOBAN_LAYER_IDENTIFIER: &str = "IntGrid";
+
+/// Whether diagonal pushes in the graveyard can squeeze between two walls.
+pub const DIAGONAL_SQUEEZE: sokoban::DiagonalSqueeze = sokoban::DiagonalSqueeze::Blocked;
+
 /// Plugin providing logic for all graveyard entities and the entire graveyard state.
 ///
 /// So, the logic for core gameplay lives here.
@@ -46,22 +64,62 @@ impl Plugin for GraveyardPlugin {
             .add_plugins((
                 control_display::ControlDisplayPlugin,
                 willo::WilloPlugin,
-                sokoban::SokobanPlugin::new(GameState::Graveyard, "IntGrid"),
+                sokoban::SokobanPlugin::new(GameState::Graveyard, SOKOBAN_LAYER_IDENTIFIER)
+                    .with_diagonal_squeeze(DIAGONAL_SQUEEZE),
                 movement_table::MovementTablePlugin,
                 gravestone::GravestonePlugin,
                 volatile::VolatilePlugin,
                 wall::WallPlugin,
                 goal::GoalPlugin,
+                move_counter::MoveCounterPlugin,
+                replay::ReplayPlugin,
                 exorcism::ExorcismPlugin,
                 wind::WindPlugin,
             ))
+            .add_plugins((
+                pit::PitPlugin,
+                teleporter::TeleporterPlugin,
+                conveyor::ConveyorPlugin,
+                coffin::CoffinPlugin,
+                path_preview::PathPreviewPlugin,
+            ))
             .add_systems(
                 Update,
                 graveyard_input
                     .run_if(in_state(GameState::Graveyard))
                     .in_set(willo::WilloSets::Input)
                     .before(FlushHistoryCommands),
-            );
+            )
+            .add_systems(OnExit(GameState::LevelTransition), set_push_rule);
+    }
+}
+
+/// The [PushRule] of a level.
+///
+/// Levels with their "ClassicPush" LDtk field set only allow pushing a single block at a time,
+/// other levels limit pushes by Willo's strength.
+pub fn level_push_rule(level: &Level) -> PushRule {
+    if level
+        .get_bool_field("ClassicPush")
+        .is_ok_and(|classic_push| *classic_push)
+    {
+        PushRule::SingleBlock
+    } else {
+        PushRule::Strength
+    }
+}
+
+fn set_push_rule(
+    mut push_rule: ResMut<PushRule>,
+    level_selection: Res<LevelSelection>,
+    ldtk_assets: Res<Assets<LdtkProject>>,
+    asset_holder: Res<AssetHolder>,
+) {
+    if let Some(level) = ldtk_assets
+        .get(&asset_holder.ldtk)
+        .and_then(|ldtk_asset| ldtk_asset.find_raw_level_by_level_selection(&level_selection))
+    {
+        *push_rule = level_push_rule(level);
     }
 }
 
@@ -70,6 +128,8 @@ impl Plugin for GraveyardPlugin {
 pub enum GraveyardAction {
     /// Undo the last grave-action or restart.
     Undo,
+    /// Redo the last undone grave-action or restart.
+    Redo,
     /// Restart the level to its initial state.
     Restart,
     /// Pause the graveyard state and open up the pause menu.
@@ -163,6 +223,9 @@ fn graveyard_input(
                         timer.set_duration(Duration::from_millis(*velocity as u64));
                     }
                 }
+            } else if gameplay_input.just_pressed(GraveyardAction::Redo) {
+                history_commands.send(HistoryCommands::Redo);
+                *willo = willo::WilloState::Waiting;
             } else if gameplay_input.just_pressed(GraveyardAction::Restart) {
                 history_commands.send(HistoryCommands::Reset);
                 *willo = willo::WilloState::Waiting;
@@ -0,0 +1,175 @@
+//! Plugin and resources for counting grave-actions and scoring them against a level's par.
+use crate::{
+    history::{FlushHistoryCommands, HistoryCommands, HistoryPlugin},
+    GameState,
+};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::Level, prelude::LdtkFields};
+
+/// Plugin for counting grave-actions and scoring them against a level's par.
+pub struct MoveCounterPlugin;
+
+impl Plugin for MoveCounterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(HistoryPlugin::<MoveCount, _>::run_in_state(
+            GameState::Graveyard,
+        ))
+        .add_systems(
+            Update,
+            count_moves
+                .run_if(in_state(GameState::Graveyard))
+                .run_if(on_event::<HistoryCommands>())
+                .after(FlushHistoryCommands),
+        )
+        .add_systems(OnExit(GameState::LevelTransition), remove_level_score);
+    }
+}
+
+/// Component counting the grave-actions it took to reach the current state of the level.
+///
+/// Willo carries the count along with its [History](crate::history::History), so undoing a
+/// grave-action also uncounts it.
+/// It is a component rather than a resource because the [HistoryPlugin] only tracks components.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Component, Reflect)]
+#[reflect(Component)]
+pub struct MoveCount(pub u32);
+
+/// Resource describing the score of the most recently completed level.
+///
+/// Inserted when the level is completed, and removed once the next level has loaded.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Resource)]
+pub struct LevelScore {
+    /// The number of grave-actions the level was completed in.
+    pub moves: u32,
+    /// The par of the level, as defined by its optional "Par" LDtk field.
+    pub par: Option<u32>,
+}
+
+impl LevelScore {
+    /// Rating of the score from one to three stars, or `None` if the level has no par.
+    ///
+    /// Meeting par earns three stars, and staying within half again of par earns two.
+    pub fn stars(&self) -> Option<u32> {
+        self.par.map(|par| {
+            if self.moves <= par {
+                3
+            } else if self.moves <= par + par / 2 {
+                2
+            } else {
+                1
+            }
+        })
+    }
+}
+
+/// The par of a level, as defined by its optional "Par" LDtk field.
+pub fn level_par(level: &Level) -> Option<u32> {
+    level
+        .get_maybe_int_field("Par")
+        .ok()
+        .copied()
+        .flatten()
+        .and_then(|par| u32::try_from(par).ok())
+}
+
+/// Counts recorded grave-actions once their previous count has been recorded to the history.
+fn count_moves(
+    mut move_count_query: Query<&mut MoveCount>,
+    mut history_commands: EventReader<HistoryCommands>,
+) {
+    let records = history_commands
+        .read()
+        .filter(|command| **command == HistoryCommands::Record)
+        .count() as u32;
+
+    for mut move_count in move_count_query.iter_mut() {
+        move_count.0 += records;
+    }
+}
+
+fn remove_level_score(mut commands: Commands) {
+    commands.remove_resource::<LevelScore>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::History;
+    use bevy::ecs::system::SystemState;
+
+    fn app_setup() -> (App, Entity) {
+        let mut app = App::new();
+
+        app.add_state::<GameState>().add_plugins(MoveCounterPlugin);
+        app.world
+            .insert_resource(NextState(Some(GameState::Graveyard)));
+        app.update();
+
+        let entity = app
+            .world
+            .spawn((MoveCount::default(), History::<MoveCount>::default()))
+            .id();
+
+        (app, entity)
+    }
+
+    fn moves(app: &App, entity: Entity) -> u32 {
+        app.world.get::<MoveCount>(entity).unwrap().0
+    }
+
+    fn send_commands(app: &mut App, commands: &[HistoryCommands]) {
+        for command in commands {
+            let mut system_state: SystemState<EventWriter<HistoryCommands>> =
+                SystemState::new(&mut app.world);
+            system_state.get_mut(&mut app.world).send(*command);
+
+            app.update();
+        }
+    }
+
+    #[test]
+    fn moves_follow_history() {
+        use HistoryCommands::*;
+
+        let (mut app, entity) = app_setup();
+
+        send_commands(&mut app, &[Record, Record, Record]);
+        assert_eq!(moves(&app, entity), 3);
+
+        send_commands(&mut app, &[Rewind, Rewind]);
+        assert_eq!(moves(&app, entity), 1);
+
+        send_commands(&mut app, &[Redo]);
+        assert_eq!(moves(&app, entity), 2);
+
+        send_commands(&mut app, &[Reset]);
+        assert_eq!(moves(&app, entity), 0);
+
+        send_commands(&mut app, &[Rewind]);
+        assert_eq!(moves(&app, entity), 2);
+
+        send_commands(&mut app, &[Rewind, Record]);
+        assert_eq!(moves(&app, entity), 2);
+    }
+
+    #[test]
+    fn stars_are_rated_against_par() {
+        let score = |moves| LevelScore {
+            moves,
+            par: Some(10),
+        };
+
+        assert_eq!(score(9).stars(), Some(3));
+        assert_eq!(score(10).stars(), Some(3));
+        assert_eq!(score(15).stars(), Some(2));
+        assert_eq!(score(16).stars(), Some(1));
+        assert_eq!(
+            LevelScore {
+                moves: 10,
+                par: None
+            }
+            .stars(),
+            None
+        );
+    }
+}
@@ -1,5 +1,14 @@
 //! Plugin and components providing functionality for the movement table, which alters Willo's
 //! abilities based off the placement of gravestones.
+//!
+//! The shape of the table is defined per level by the `Table` entity's LDtk fields:
+//! - "Ranks" and "Files" are the number of ranks and files
+//! - "Rank_directions" and "File_directions" are the directions of each rank from the top down,
+//!   and each file from the left
+//!
+//! Directions may be diagonal (like `UpLeft`) and may repeat, and `None` (or a missing entry) makes
+//! the rank or file a no-op.
+//! When a table has no directions, they follow [DIRECTION_ORDER] on a 4x4 table.
 use crate::{
     from_component::FromComponentSet,
     graveyard::{
@@ -37,7 +46,7 @@ impl Plugin for MovementTablePlugin {
     }
 }
 
-/// Defines the order that the four [Direction]s go in on the table's rank and file.
+/// Defines the default order that the four [Direction]s go in on the table's rank and file.
 pub const DIRECTION_ORDER: [Direction; 4] = [
     Direction::Up,
     Direction::Left,
@@ -45,18 +54,165 @@ pub const DIRECTION_ORDER: [Direction; 4] = [
     Direction::Right,
 ];
 
+/// The shape of a movement table and the directions of its ranks and files.
+///
+/// See the [module-level docs](self) for how this is defined in LDtk.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub struct MovementTableLayout {
+    /// Direction of each rank, from the top rank down, or `None` for no-op ranks.
+    pub ranks: Vec<Option<Direction>>,
+    /// Direction of each file, from the leftmost file right, or `None` for no-op files.
+    pub files: Vec<Option<Direction>>,
+}
+
+impl Default for MovementTableLayout {
+    fn default() -> Self {
+        MovementTableLayout {
+            ranks: DIRECTION_ORDER.map(Some).to_vec(),
+            files: DIRECTION_ORDER.map(Some).to_vec(),
+        }
+    }
+}
+
+fn direction_from_ldtk(direction: &str) -> Option<Direction> {
+    match direction {
+        "Up" => Some(Direction::Up),
+        "Left" => Some(Direction::Left),
+        "Down" => Some(Direction::Down),
+        "Right" => Some(Direction::Right),
+        "UpLeft" => Some(Direction::UpLeft),
+        "UpRight" => Some(Direction::UpRight),
+        "DownLeft" => Some(Direction::DownLeft),
+        "DownRight" => Some(Direction::DownRight),
+        "None" | "" => None,
+        d => {
+            warn!("encountered bad movement table direction, treating it as a no-op: {d}");
+            None
+        }
+    }
+}
+
+fn table_axis(
+    entity_instance: &EntityInstance,
+    count_field: &str,
+    directions_field: &str,
+) -> Vec<Option<Direction>> {
+    let directions = entity_instance
+        .get_maybe_strings_field(directions_field)
+        .unwrap_or(&[]);
+
+    let count = entity_instance
+        .get_maybe_int_field(count_field)
+        .ok()
+        .copied()
+        .flatten()
+        .map(|count| count.max(0) as usize)
+        .unwrap_or(if directions.is_empty() {
+            DIRECTION_ORDER.len()
+        } else {
+            directions.len()
+        });
+
+    (0..count)
+        .map(|i| {
+            if directions.is_empty() {
+                Some(DIRECTION_ORDER[i % DIRECTION_ORDER.len()])
+            } else {
+                directions
+                    .get(i)
+                    .and_then(|direction| direction.as_deref())
+                    .and_then(direction_from_ldtk)
+            }
+        })
+        .collect()
+}
+
+impl From<&EntityInstance> for MovementTableLayout {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        MovementTableLayout {
+            ranks: table_axis(entity_instance, "Ranks", "Rank_directions"),
+            files: table_axis(entity_instance, "Files", "File_directions"),
+        }
+    }
+}
+
 /// Component that marks the movement table and stores the current placement of gravestones.
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Component)]
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash, Component)]
 pub struct MovementTable {
-    /// 4x4 table marking the locations of gravestones, identified by the [GraveId] they are
-    /// associated with
-    pub table: [[Option<GraveId>; 4]; 4],
+    /// The shape of the table and the directions of its ranks and files.
+    pub layout: MovementTableLayout,
+    /// Table marking the locations of gravestones, identified by the [GraveId] they are
+    /// associated with.
+    ///
+    /// Indexed by rank, then file, matching the dimensions of the layout.
+    pub table: Vec<Vec<Option<GraveId>>>,
+}
+
+impl From<&EntityInstance> for MovementTable {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        MovementTable::new(MovementTableLayout::from(entity_instance))
+    }
+}
+
+impl MovementTable {
+    /// Construct an empty table with the given layout.
+    pub fn new(layout: MovementTableLayout) -> MovementTable {
+        let table = vec![vec![None; layout.files.len()]; layout.ranks.len()];
+
+        MovementTable { layout, table }
+    }
+
+    /// Construct the table from the positions of gravestones relative to the table's position.
+    ///
+    /// Gravestones outside of the table's grid are ignored.
+    pub fn from_gravestones(
+        table_grid_coords: GridCoords,
+        layout: MovementTableLayout,
+        gravestones: impl IntoIterator<Item = (GridCoords, GraveId)>,
+    ) -> MovementTable {
+        let mut movement_table = MovementTable::new(layout);
+        for (gravestone_grid_coords, grave_id) in gravestones {
+            let diff = gravestone_grid_coords - table_grid_coords;
+            let x_index = diff.x - 1;
+            let y_index = -1 - diff.y;
+            if let Some(cell) = usize::try_from(y_index)
+                .ok()
+                .zip(usize::try_from(x_index).ok())
+                .and_then(|(y, x)| movement_table.table.get_mut(y)?.get_mut(x))
+            {
+                // key block is in table
+                *cell = Some(grave_id);
+            }
+        }
+
+        movement_table
+    }
+
+    /// Iterates over the directions Willo moves in for the rank move of the given grave-action.
+    pub fn rank_directions(&self, grave_id: GraveId) -> impl Iterator<Item = Direction> + '_ {
+        self.table
+            .iter()
+            .zip(self.layout.ranks.iter())
+            .filter(move |(rank, _)| rank.contains(&Some(grave_id)))
+            .filter_map(|(_, direction)| *direction)
+    }
+
+    /// Iterates over the directions Willo moves in for the file move of the given grave-action.
+    pub fn file_directions(&self, grave_id: GraveId) -> impl Iterator<Item = Direction> + '_ {
+        self.table.iter().flat_map(move |rank| {
+            rank.iter()
+                .zip(self.layout.files.iter())
+                .filter(move |(cell, _)| **cell == Some(grave_id))
+                .filter_map(|(_, direction)| *direction)
+        })
+    }
 }
 
 #[derive(Clone, Default, Bundle, LdtkEntity)]
 struct MovementTableBundle {
     #[grid_coords]
     grid_coords: GridCoords,
+    #[from_entity_instance]
     move_table: MovementTable,
     #[sprite_sheet_bundle]
     sprite_sheet_bundle: SpriteSheetBundle,
@@ -67,21 +223,17 @@ fn movement_table_update(
     input_block_query: Query<(&GridCoords, &GraveId, &Volatile)>,
 ) {
     for (table_grid_coords, mut table) in table_query.iter_mut() {
-        let mut new_table = [[None; 4]; 4];
-        for (input_grid_coords, input_block, volatile) in input_block_query.iter() {
-            if volatile.is_solid() {
-                let diff = *input_grid_coords - *table_grid_coords;
-                let x_index = diff.x - 1;
-                let y_index = -1 - diff.y;
-                if (0..4).contains(&x_index) && (0..4).contains(&y_index) {
-                    // key block is in table
-                    new_table[y_index as usize][x_index as usize] = Some(*input_block);
-                }
-            }
-        }
+        let new_table = MovementTable::from_gravestones(
+            *table_grid_coords,
+            table.layout.clone(),
+            input_block_query
+                .iter()
+                .filter(|(.., volatile)| volatile.is_solid())
+                .map(|(grid_coords, grave_id, _)| (*grid_coords, *grave_id)),
+        );
 
-        if table.table != new_table {
-            table.table = new_table;
+        if *table != new_table {
+            *table = new_table;
         }
     }
 }
@@ -106,27 +258,19 @@ fn move_willo_by_table(
             if timer.0.finished() {
                 match *willo {
                     WilloState::RankMove(key) => {
-                        for (i, rank) in table.table.iter().enumerate() {
-                            if rank.contains(&Some(key)) {
-                                let direction = DIRECTION_ORDER[i];
-                                sokoban_commands.move_block(entity, direction);
-                                *willo_animation_state = WilloAnimationState::Idle(direction);
-                            }
+                        for direction in table.rank_directions(key) {
+                            sokoban_commands.move_block(entity, direction);
+                            *willo_animation_state = WilloAnimationState::Idle(direction);
                         }
                         *willo = WilloState::FileMove(key);
                         timer.0.reset();
                     }
                     WilloState::FileMove(key) => {
-                        for rank in table.table.iter() {
-                            for (i, cell) in rank.iter().enumerate() {
-                                if *cell == Some(key) {
-                                    let direction = DIRECTION_ORDER[i];
-                                    sokoban_commands.move_block(entity, direction);
-                                    *willo_animation_state = WilloAnimationState::Idle(direction);
-                                }
-                            }
+                        for direction in table.file_directions(key) {
+                            sokoban_commands.move_block(entity, direction);
+                            *willo_animation_state = WilloAnimationState::Idle(direction);
                         }
-                        *willo = WilloState::Waiting;
+                        *willo = WilloState::ConveyorMove;
                         timer.0.reset();
                     }
                     _ => {}
@@ -0,0 +1,368 @@
+//! Plugin previewing grave-actions on the board while their buttons are hovered.
+//!
+//! While Willo is waiting for input, hovering over or pressing a `UiAction<GraveId>` button on the
+//! control display shows translucent copies of Willo where the rank move and the file move would
+//! leave them, and marks where every pushed gravestone would end up.
+//! If Willo would be exorcised, their last copy is tinted red.
+//!
+//! Previews are computed from the current [MovementTable] with [SokobanPreview], so nothing moves
+//! until the grave-action is actually performed.
+use crate::{
+    graveyard::{
+        gravestone::GraveId, movement_table::MovementTable, volatile::Volatile, willo::WilloState,
+        SOKOBAN_LAYER_IDENTIFIER,
+    },
+    sokoban::{Direction, MovePreview, SokobanBlock, SokobanPreview},
+    ui::action::UiAction,
+    GameState,
+};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_ecs_ldtk::{prelude::*, utils::grid_coords_to_translation};
+
+/// Plugin previewing grave-actions on the board while their buttons are hovered.
+///
+/// See the [module-level docs](self) for more info.
+pub struct PathPreviewPlugin;
+
+impl Plugin for PathPreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShownPathPreview>()
+            .add_systems(
+                Update,
+                update_path_preview.run_if(in_state(GameState::Graveyard)),
+            )
+            .add_systems(OnExit(GameState::Graveyard), despawn_path_preview);
+    }
+}
+
+/// Color of the translucent copies of Willo and the pushed gravestone markers.
+const GHOST_COLOR: Color = Color::rgba(1., 1., 1., 0.4);
+
+/// Color of the copy of Willo that would be exorcised.
+const EXORCISED_GHOST_COLOR: Color = Color::rgba(1., 0.2, 0.2, 0.6);
+
+/// The predicted outcome of a grave-action, see [GraveActionPreviewer].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GraveActionPreview {
+    /// Where Willo would be after the rank move, or `None` if the rank move wouldn't move them.
+    pub rank_move: Option<GridCoords>,
+    /// Where Willo would be after the file move, or `None` if the file move wouldn't move them.
+    pub file_move: Option<GridCoords>,
+    /// The blocks Willo would push, and where they would end up.
+    pub pushed: Vec<(Entity, GridCoords)>,
+    /// Whether Willo would end up on an exorcism tile.
+    ///
+    /// Exorcised Willos don't make the rest of their grave-action.
+    pub exorcised: bool,
+}
+
+impl GraveActionPreview {
+    fn push(&mut self, entity: Entity, destination: GridCoords) {
+        match self.pushed.iter_mut().find(|(pushed, _)| *pushed == entity) {
+            Some((_, pushed_destination)) => *pushed_destination = destination,
+            None => self.pushed.push((entity, destination)),
+        }
+    }
+}
+
+/// System parameter predicting the outcome of grave-actions without performing them.
+#[derive(SystemParam)]
+pub struct GraveActionPreviewer<'w, 's> {
+    sokoban_preview: SokobanPreview<'w, 's>,
+    table_query: Query<'w, 's, &'static MovementTable>,
+    willo_query: Query<'w, 's, Entity, With<WilloState>>,
+    exorcism_query: Query<'w, 's, (&'static GridCoords, &'static Volatile), Without<SokobanBlock>>,
+}
+
+impl GraveActionPreviewer<'_, '_> {
+    /// Predicts the outcome of performing the given grave-action right now.
+    ///
+    /// Returns `None` if there is no Willo or movement table.
+    pub fn preview(&self, grave_id: GraveId) -> Option<GraveActionPreview> {
+        let table = self.table_query.get_single().ok()?;
+        let willo = self.willo_query.get_single().ok()?;
+
+        let rank_directions: Vec<Direction> = table.rank_directions(grave_id).collect();
+        let file_directions: Vec<Direction> = table.file_directions(grave_id).collect();
+
+        let move_previews = self.sokoban_preview.preview_moves(
+            willo,
+            rank_directions.iter().chain(&file_directions).copied(),
+        );
+
+        let (rank_previews, file_previews) = move_previews.split_at(rank_directions.len());
+
+        let mut grave_action_preview = GraveActionPreview::default();
+        let mut stops = [None, None];
+
+        for (stop, previews) in stops.iter_mut().zip([rank_previews, file_previews]) {
+            for move_preview in previews {
+                if let MovePreview::Moved(moved_entities) = move_preview {
+                    for (entity, path) in moved_entities {
+                        let destination = *path
+                            .last()
+                            .expect("moved entity should have moved at least once");
+
+                        if *entity == willo {
+                            *stop = Some(destination);
+                        } else {
+                            grave_action_preview.push(*entity, destination);
+                        }
+                    }
+                }
+            }
+
+            let exorcised = stop.is_some_and(|grid_coords| {
+                self.is_exorcism_tile(grid_coords)
+                    // gravestones pushed onto exorcism tiles sublimate them
+                    && !grave_action_preview
+                        .pushed
+                        .iter()
+                        .any(|(_, destination)| *destination == grid_coords)
+            });
+
+            if exorcised {
+                grave_action_preview.exorcised = true;
+                break;
+            }
+        }
+
+        let [rank_move, file_move] = stops;
+
+        Some(GraveActionPreview {
+            rank_move,
+            file_move,
+            ..grave_action_preview
+        })
+    }
+
+    fn is_exorcism_tile(&self, grid_coords: GridCoords) -> bool {
+        self.exorcism_query
+            .iter()
+            .any(|(tile_grid_coords, volatile)| {
+                *tile_grid_coords == grid_coords && volatile.is_solid()
+            })
+    }
+}
+
+/// Resource storing the preview currently shown on the board.
+#[derive(Clone, Debug, Default, Resource)]
+struct ShownPathPreview(Option<GraveActionPreview>);
+
+/// Component marking the entities drawing the path preview.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Component)]
+struct PathPreviewGhost;
+
+fn update_path_preview(
+    mut commands: Commands,
+    button_query: Query<(&UiAction<GraveId>, &Interaction)>,
+    willo_query: Query<(
+        &WilloState,
+        &TextureAtlasSprite,
+        &Handle<TextureAtlas>,
+        &Transform,
+        Option<&Parent>,
+    )>,
+    block_transforms: Query<&Transform, With<SokobanBlock>>,
+    ghost_query: Query<Entity, With<PathPreviewGhost>>,
+    previewer: GraveActionPreviewer,
+    layers: Query<&LayerMetadata>,
+    mut shown: ResMut<ShownPathPreview>,
+) {
+    let willo = willo_query.get_single().ok();
+
+    let preview = match willo {
+        Some((WilloState::Waiting, ..)) => button_query
+            .iter()
+            .find(|(_, interaction)| {
+                matches!(interaction, Interaction::Hovered | Interaction::Pressed)
+            })
+            .and_then(|(action, _)| previewer.preview(**action)),
+        _ => None,
+    };
+
+    if shown.0 == preview {
+        return;
+    }
+
+    for ghost in ghost_query.iter() {
+        commands.entity(ghost).despawn_recursive();
+    }
+
+    shown.0 = preview.clone();
+
+    let (
+        Some(preview),
+        Some((_, sprite, texture_atlas, willo_transform, parent)),
+        Some(LayerMetadata { grid_size, .. }),
+    ) = (
+        preview,
+        willo,
+        layers
+            .iter()
+            .find(|layer| layer.identifier == SOKOBAN_LAYER_IDENTIFIER),
+    )
+    else {
+        return;
+    };
+
+    let translation = |grid_coords: GridCoords, z: f32| {
+        grid_coords_to_translation(grid_coords, IVec2::splat(*grid_size)).extend(z)
+    };
+
+    let mut ghosts = Vec::new();
+
+    let stops: Vec<GridCoords> = [preview.rank_move, preview.file_move]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    for (i, grid_coords) in stops.iter().enumerate() {
+        let color = if preview.exorcised && i == stops.len() - 1 {
+            EXORCISED_GHOST_COLOR
+        } else {
+            GHOST_COLOR
+        };
+
+        ghosts.push(
+            commands
+                .spawn(SpriteSheetBundle {
+                    sprite: TextureAtlasSprite {
+                        color,
+                        ..sprite.clone()
+                    },
+                    texture_atlas: texture_atlas.clone(),
+                    transform: Transform::from_translation(translation(
+                        *grid_coords,
+                        willo_transform.translation.z,
+                    )),
+                    ..default()
+                })
+                .insert(PathPreviewGhost)
+                .id(),
+        );
+    }
+
+    for (pushed, grid_coords) in preview.pushed {
+        let z = block_transforms
+            .get(pushed)
+            .map_or(willo_transform.translation.z, |transform| {
+                transform.translation.z
+            });
+
+        ghosts.push(
+            commands
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: GHOST_COLOR,
+                        custom_size: Some(Vec2::splat(*grid_size as f32 / 2.)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(translation(grid_coords, z)),
+                    ..default()
+                })
+                .insert(PathPreviewGhost)
+                .id(),
+        );
+    }
+
+    // ghosts live alongside Willo so they share the level's transform
+    if let Some(parent) = parent {
+        commands.entity(parent.get()).push_children(&ghosts);
+    }
+}
+
+fn despawn_path_preview(
+    mut commands: Commands,
+    ghost_query: Query<Entity, With<PathPreviewGhost>>,
+    mut shown: ResMut<ShownPathPreview>,
+) {
+    for ghost in ghost_query.iter() {
+        commands.entity(ghost).despawn_recursive();
+    }
+
+    shown.0 = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{graveyard::movement_table::MovementTableLayout, sokoban::SokobanPlugin};
+    use bevy::ecs::system::SystemState;
+
+    fn app_setup() -> App {
+        let mut app = App::new();
+
+        app.add_state::<GameState>().add_plugins(SokobanPlugin::new(
+            GameState::Graveyard,
+            SOKOBAN_LAYER_IDENTIFIER,
+        ));
+
+        app.world.spawn(LayerMetadata {
+            c_wid: 5,
+            c_hei: 5,
+            grid_size: 32,
+            identifier: SOKOBAN_LAYER_IDENTIFIER.to_string(),
+            ..default()
+        });
+
+        app.world
+            .insert_resource(NextState(Some(GameState::Graveyard)));
+
+        app
+    }
+
+    #[test]
+    fn grave_action_preview_follows_table() {
+        let mut app = app_setup();
+
+        let mut movement_table = MovementTable::new(MovementTableLayout::default());
+        // rank: up, file: right
+        movement_table.table[0][3] = Some(GraveId::North);
+        app.world.spawn(movement_table);
+
+        let willo = app
+            .world
+            .spawn((
+                GridCoords::new(1, 1),
+                SokobanBlock::Dynamic,
+                WilloState::Waiting,
+            ))
+            .id();
+        let gravestone = app
+            .world
+            .spawn((GridCoords::new(1, 2), SokobanBlock::Dynamic, GraveId::North))
+            .id();
+        app.world.spawn((GridCoords::new(2, 2), Volatile::Solid));
+
+        app.update();
+
+        let mut system_state: SystemState<GraveActionPreviewer> = SystemState::new(&mut app.world);
+        let previewer: GraveActionPreviewer = system_state.get(&app.world);
+
+        assert_eq!(
+            previewer.preview(GraveId::North),
+            Some(GraveActionPreview {
+                rank_move: Some(GridCoords::new(1, 2)),
+                file_move: Some(GridCoords::new(2, 2)),
+                pushed: vec![(gravestone, GridCoords::new(1, 3))],
+                exorcised: true,
+            })
+        );
+
+        // grave-actions missing from the table don't move Willo
+        assert_eq!(
+            previewer.preview(GraveId::West),
+            Some(GraveActionPreview::default())
+        );
+
+        assert_eq!(
+            *app.world.entity(willo).get::<GridCoords>().unwrap(),
+            GridCoords::new(1, 1)
+        );
+        assert_eq!(
+            *app.world.entity(gravestone).get::<GridCoords>().unwrap(),
+            GridCoords::new(1, 2)
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! Plugin providing functionality for pits, which swallow gravestones and become floor.
+//!
+//! Open pits block Willo.
+//! Once a gravestone is pushed into a pit, the gravestone is sublimated and the pit is filled,
+//! making it walkable floor for the rest of the level.
+//! Both changes are tracked by history, so they can be undone.
+use crate::{
+    graveyard::{
+        gravestone::GraveId,
+        volatile::{Sublimation, Volatile},
+    },
+    history::{History, HistoryPlugin},
+    sokoban::{SokobanBlock, SokobanSets},
+    GameState,
+};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_tilemap::tiles::TileVisible;
+use std::ops::DerefMut;
+
+/// Plugin providing functionality for pits, which swallow gravestones and become floor.
+pub struct PitPlugin;
+
+/// IntGrid value of the LDtk "IntGrid" layer that is spawned as a pit.
+pub const PIT_INT_GRID_VALUE: i32 = 7;
+
+impl Plugin for PitPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(HistoryPlugin::<Pit, _>::run_in_state(GameState::Graveyard))
+            .add_systems(
+                Update,
+                (
+                    fill_pits_with_gravestones
+                        .run_if(in_state(GameState::Graveyard))
+                        .after(SokobanSets::LogicalMovement)
+                        .before(Sublimation),
+                    update_pit_blocks.run_if(in_state(GameState::Graveyard)),
+                    visually_fill_pit_tiles.run_if(in_state(GameState::Graveyard)),
+                ),
+            )
+            .register_ldtk_int_cell::<PitBundle>(PIT_INT_GRID_VALUE);
+    }
+}
+
+/// Component defining whether a pit has been filled by a gravestone.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Component)]
+pub enum Pit {
+    /// The pit is a [SokobanBlock::Hole], blocking Willo and swallowing gravestones.
+    #[default]
+    Open,
+    /// The pit has swallowed a gravestone, and is now floor.
+    Filled,
+}
+
+#[derive(Clone, Default, Bundle, LdtkIntCell)]
+struct PitBundle {
+    pit: Pit,
+    pit_history: History<Pit>,
+}
+
+/// Fills open pits that solid gravestones have been pushed into, sublimating the gravestones.
+pub fn fill_pits<P: DerefMut<Target = Pit>, V: DerefMut<Target = Volatile>>(
+    pits: impl IntoIterator<Item = (GridCoords, P)>,
+    gravestones: &mut [(GridCoords, V)],
+) {
+    for (pit_grid_coords, mut pit) in pits {
+        if *pit != Pit::Open {
+            continue;
+        }
+
+        if let Some((_, volatile)) = gravestones
+            .iter_mut()
+            .find(|(grid_coords, volatile)| *grid_coords == pit_grid_coords && volatile.is_solid())
+        {
+            volatile.sublimate();
+            *pit = Pit::Filled;
+        }
+    }
+}
+
+fn fill_pits_with_gravestones(
+    mut pit_query: Query<(&GridCoords, &mut Pit)>,
+    mut gravestone_query: Query<(&GridCoords, &mut Volatile), With<GraveId>>,
+) {
+    let mut gravestones: Vec<_> = gravestone_query
+        .iter_mut()
+        .map(|(grid_coords, volatile)| (*grid_coords, volatile))
+        .collect();
+
+    fill_pits(
+        pit_query
+            .iter_mut()
+            .map(|(grid_coords, pit)| (*grid_coords, pit)),
+        &mut gravestones,
+    );
+}
+
+fn update_pit_blocks(mut commands: Commands, pit_query: Query<(Entity, &Pit), Changed<Pit>>) {
+    for (entity, pit) in pit_query.iter() {
+        match pit {
+            Pit::Open => {
+                commands.entity(entity).insert(SokobanBlock::Hole);
+            }
+            Pit::Filled => {
+                commands.entity(entity).remove::<SokobanBlock>();
+            }
+        }
+    }
+}
+
+fn visually_fill_pit_tiles(mut pit_tile_query: Query<(&mut TileVisible, &Pit), Changed<Pit>>) {
+    for (mut visibility, pit) in pit_tile_query.iter_mut() {
+        visibility.0 = *pit == Pit::Open;
+    }
+}
@@ -0,0 +1,365 @@
+//! Plugin and types for recording a level's inputs to replay files and playing them back.
+//!
+//! Replays are JSON files storing the level identifier and a compact string of inputs, one
+//! character per input:
+//! - the gravestone's key (`Q`, `W`, `E`, `A`, `S` or `D`) for grave-actions
+//! - `Z` for undo
+//! - `Y` for redo
+//! - `R` for restart
+//!
+//! Playback feeds the inputs back through the same [ActionState]s that the player's input uses.
+use crate::{
+    graveyard::{
+        gravestone::GraveId,
+        willo::{MovementTimer, WilloSets, WilloState},
+        GraveyardAction,
+    },
+    history::{FlushHistoryCommands, HistoryCommands},
+    utils::current_level_identifier,
+    AssetHolder, GameState,
+};
+use bevy::{app::AppExit, prelude::*};
+use bevy_ecs_ldtk::prelude::*;
+use leafwing_input_manager::{plugin::InputManagerSystem, prelude::*};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{collections::VecDeque, path::Path};
+use thiserror::Error;
+
+/// Plugin for recording a level's inputs to replay files and playing them back.
+///
+/// See [module-level docs](self) for more info.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayRecording>()
+            .add_systems(
+                PreUpdate,
+                play_replay
+                    .run_if(in_state(GameState::Graveyard))
+                    .run_if(resource_exists::<ReplayPlayback>())
+                    .in_set(InputManagerSystem::ManualControl),
+            )
+            .add_systems(
+                Update,
+                record_inputs
+                    .run_if(in_state(GameState::Graveyard))
+                    .run_if(on_event::<HistoryCommands>())
+                    .run_if(not(resource_exists::<ReplayPlayback>()))
+                    .after(WilloSets::Input)
+                    .before(FlushHistoryCommands),
+            )
+            // replays passed in at startup play through the first level transition
+            .add_systems(
+                OnTransition {
+                    from: GameState::Graveyard,
+                    to: GameState::LevelTransition,
+                },
+                stop_playback,
+            )
+            .add_systems(
+                OnTransition {
+                    from: GameState::LevelSelect,
+                    to: GameState::LevelTransition,
+                },
+                stop_playback,
+            )
+            .add_systems(OnExit(GameState::LevelTransition), start_recording)
+            .add_systems(OnExit(GameState::Graveyard), save_recording)
+            .add_systems(
+                Last,
+                save_recording
+                    .run_if(in_state(GameState::Graveyard))
+                    .run_if(on_event::<AppExit>()),
+            );
+    }
+}
+
+/// Errors encountered when loading or writing replay files.
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    /// The platform has no data directory to store replay files in.
+    #[error("unable to find the user's data directory")]
+    NoDataDirectory,
+    /// The replay file could not be read or written.
+    #[error("replay file io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The replay file is not valid JSON, or contains unknown inputs.
+    #[error("replay file is corrupted: {0}")]
+    Corrupted(#[from] serde_json::Error),
+}
+
+/// A single input stored in a replay.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ReplayInput {
+    /// A grave-action was performed.
+    GraveAction(GraveId),
+    /// The last grave-action or restart was undone.
+    Undo,
+    /// The last undone grave-action or restart was redone.
+    Redo,
+    /// The level was restarted.
+    Restart,
+}
+
+impl ReplayInput {
+    /// The character representing this input in replay files.
+    pub fn to_char(self) -> char {
+        match self {
+            ReplayInput::GraveAction(grave_id) => {
+                grave_id.ldtk_identifier().chars().next().unwrap()
+            }
+            ReplayInput::Undo => 'Z',
+            ReplayInput::Redo => 'Y',
+            ReplayInput::Restart => 'R',
+        }
+    }
+
+    /// Parses an input from its character in replay files.
+    pub fn from_char(c: char) -> Option<ReplayInput> {
+        match c {
+            'Z' => Some(ReplayInput::Undo),
+            'Y' => Some(ReplayInput::Redo),
+            'R' => Some(ReplayInput::Restart),
+            c => GraveId::ALL
+                .into_iter()
+                .find(|grave_id| grave_id.ldtk_identifier().starts_with(c))
+                .map(ReplayInput::GraveAction),
+        }
+    }
+}
+
+/// Recorded inputs for a single level.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Replay {
+    /// Identifier of the level the inputs were recorded in.
+    pub level: String,
+    /// The inputs, in the order they were performed.
+    #[serde(serialize_with = "serialize_inputs")]
+    #[serde(deserialize_with = "deserialize_inputs")]
+    pub inputs: Vec<ReplayInput>,
+}
+
+fn serialize_inputs<S: Serializer>(
+    inputs: &[ReplayInput],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(
+        &inputs
+            .iter()
+            .map(|input| input.to_char())
+            .collect::<String>(),
+    )
+}
+
+fn deserialize_inputs<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<ReplayInput>, D::Error> {
+    String::deserialize(deserializer)?
+        .chars()
+        .map(|c| {
+            ReplayInput::from_char(c)
+                .ok_or_else(|| serde::de::Error::custom(format!("unknown replay input {c:?}")))
+        })
+        .collect()
+}
+
+impl Replay {
+    /// Loads a replay from the given file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Replay, ReplayError> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Writes this replay to the given file, replacing any existing one.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, serde_json::to_string(self)?)?;
+
+        Ok(())
+    }
+}
+
+/// Resource storing the inputs performed in the current level so far.
+///
+/// These are written to `replays/<level identifier>.json` in the game's data directory when the
+/// level is completed or left, or when the game exits.
+/// Nothing is recorded until a level has been loaded through the level transition.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Deref, Resource)]
+pub struct ReplayRecording(Option<Replay>);
+
+impl ReplayRecording {
+    /// Constructs an empty recording of the level with the given identifier.
+    pub fn new(level: impl Into<String>) -> ReplayRecording {
+        ReplayRecording(Some(Replay {
+            level: level.into(),
+            inputs: Vec::new(),
+        }))
+    }
+}
+
+/// Resource that plays back a replay while it exists.
+///
+/// Inputs are performed whenever Willo is idle, spaced out by the [MovementTimer] duration.
+/// The resource is removed once every input has been performed, or when the level is left.
+/// Nothing is recorded to the [ReplayRecording] while a replay is playing.
+#[derive(Clone, Debug, Resource)]
+pub struct ReplayPlayback {
+    inputs: VecDeque<ReplayInput>,
+    timer: Timer,
+}
+
+impl ReplayPlayback {
+    /// Constructs a playback of the given replay.
+    pub fn new(replay: Replay) -> ReplayPlayback {
+        ReplayPlayback {
+            inputs: replay.inputs.into(),
+            timer: MovementTimer::default().0,
+        }
+    }
+}
+
+fn write_recording(replay: &Replay) -> Result<(), ReplayError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = crate::save::game_data_dir()
+            .ok_or(ReplayError::NoDataDirectory)?
+            .join("replays")
+            .join(format!("{}.json", replay.level));
+
+        replay.write(path)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = replay;
+        Ok(())
+    }
+}
+
+fn start_recording(
+    mut recording: ResMut<ReplayRecording>,
+    level_selection: Res<LevelSelection>,
+    ldtk_assets: Res<Assets<LdtkProject>>,
+    asset_holder: Res<AssetHolder>,
+) {
+    *recording = current_level_identifier(&level_selection, &ldtk_assets, &asset_holder)
+        .map(ReplayRecording::new)
+        .unwrap_or_default();
+}
+
+fn save_recording(recording: Res<ReplayRecording>) {
+    let Some(replay) = &recording.0 else {
+        return;
+    };
+
+    if let Err(e) = write_recording(replay) {
+        warn!("unable to write replay file: {e}");
+    }
+}
+
+fn record_inputs(
+    mut recording: ResMut<ReplayRecording>,
+    willo_query: Query<&WilloState>,
+    mut history_commands: EventReader<HistoryCommands>,
+) {
+    let Some(replay) = &mut recording.0 else {
+        return;
+    };
+
+    for command in history_commands.read() {
+        let input = match command {
+            HistoryCommands::Record => match willo_query.get_single() {
+                Ok(WilloState::RankMove(grave_id)) => ReplayInput::GraveAction(*grave_id),
+                _ => continue,
+            },
+            HistoryCommands::Rewind => ReplayInput::Undo,
+            HistoryCommands::Redo => ReplayInput::Redo,
+            HistoryCommands::Reset => ReplayInput::Restart,
+        };
+
+        replay.inputs.push(input);
+    }
+}
+
+fn stop_playback(mut commands: Commands) {
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+fn play_replay(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    willo_query: Query<(&WilloState, &MovementTimer)>,
+    mut grave_input: ResMut<ActionState<GraveId>>,
+    mut graveyard_input: ResMut<ActionState<GraveyardAction>>,
+    time: Res<Time>,
+) {
+    playback.timer.tick(time.delta());
+
+    let Ok((willo, movement_timer)) = willo_query.get_single() else {
+        return;
+    };
+
+    let willo_idle =
+        matches!(willo, WilloState::Waiting | WilloState::Dead) && movement_timer.0.finished();
+
+    if !willo_idle || !playback.timer.finished() {
+        return;
+    }
+
+    match playback.inputs.pop_front() {
+        Some(input) => press_input(input, &mut grave_input, &mut graveyard_input),
+        None => {
+            commands.remove_resource::<ReplayPlayback>();
+            return;
+        }
+    }
+
+    playback.timer.reset();
+}
+
+/// Presses the action that performs the given input.
+pub(crate) fn press_input(
+    input: ReplayInput,
+    grave_input: &mut ActionState<GraveId>,
+    graveyard_input: &mut ActionState<GraveyardAction>,
+) {
+    match input {
+        ReplayInput::GraveAction(grave_id) => grave_input.press(grave_id),
+        ReplayInput::Undo => graveyard_input.press(GraveyardAction::Undo),
+        ReplayInput::Redo => graveyard_input.press(GraveyardAction::Redo),
+        ReplayInput::Restart => graveyard_input.press(GraveyardAction::Restart),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_inputs_are_compact() {
+        let replay = Replay {
+            level: "Jaw".to_string(),
+            inputs: vec![
+                ReplayInput::GraveAction(GraveId::North),
+                ReplayInput::GraveAction(GraveId::West),
+                ReplayInput::Undo,
+                ReplayInput::Redo,
+                ReplayInput::Restart,
+                ReplayInput::GraveAction(GraveId::East),
+            ],
+        };
+
+        let json = serde_json::to_string(&replay).unwrap();
+
+        assert_eq!(json, r#"{"level":"Jaw","inputs":"WAZYRD"}"#);
+        assert_eq!(serde_json::from_str::<Replay>(&json).unwrap(), replay);
+    }
+
+    #[test]
+    fn unknown_replay_input_is_an_error() {
+        assert!(serde_json::from_str::<Replay>(r#"{"level":"Jaw","inputs":"WAX"}"#).is_err());
+    }
+}
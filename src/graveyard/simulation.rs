@@ -0,0 +1,1110 @@
+//! ECS-free simulation of graveyard puzzles.
+//!
+//! [GraveyardSimulation] steps a graveyard one grave-action at a time, with none of the frame
+//! timing, animation, or history of the graveyard plugins.
+//! This makes it suitable for tools, tests and solvers that need to play levels deterministically.
+//!
+//! The plugins share their core logic with the simulation:
+//! - pushes, slides, teleports, one-way gates and multi-cell coffins are resolved, and limited by
+//!   the [PushRule], by [limited_move_collision_map_entry]
+//! - the movement table is built and read by [MovementTable]
+//! - exorcisms are resolved by [sublimate_collisions]
+//! - conveyors move the blocks given by [conveyor_moves]
+//! - pits are [SokobanBlock::Hole]s, filled by [fill_pits]
+//! - goals and exit gates are checked by [meeting_gravestone] and [level_complete]
+use crate::{
+    graveyard::{
+        coffin::{coffin_footprint, coffin_weight},
+        conveyor::{conveyor_moves, CONVEYOR_INT_GRID_VALUES},
+        exorcism::EXORCISM_INT_GRID_VALUE,
+        goal::{goal_grave_id, level_complete, meeting_gravestone},
+        gravestone::GraveId,
+        level_push_rule,
+        movement_table::{MovementTable, MovementTableLayout},
+        pit::{fill_pits, Pit, PIT_INT_GRID_VALUE},
+        volatile::{sublimate_collisions, Volatile},
+        wall::{ICE_INT_GRID_VALUE, ONE_WAY_INT_GRID_VALUES, WALL_INT_GRID_VALUES},
+        willo::willo_strength,
+        DIAGONAL_SQUEEZE, SOKOBAN_LAYER_IDENTIFIER,
+    },
+    sokoban::{
+        limited_move_collision_map_entry, CollisionMap, Direction, Footprint, Footprints, PushRule,
+        SokobanBlock, Strength, Teleporter, Terrain, Weight,
+    },
+};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::Level, prelude::*, utils::ldtk_grid_coords_to_grid_coords};
+use thiserror::Error;
+
+/// Errors encountered when building a [GraveyardSimulation] from an LDtk level.
+#[derive(Clone, Eq, PartialEq, Debug, Hash, Error)]
+pub enum SimulationError {
+    /// The level has no layer instances, it may need to be loaded from its external level file.
+    #[error("level has no layer instances, it may need to be loaded from its external level file")]
+    MissingLayerInstances,
+    /// The level has no sokoban grid layer.
+    #[error("level has no IntGrid layer")]
+    MissingSokobanLayer,
+    /// The level has no Willo entity.
+    #[error("level has no Willo entity")]
+    MissingWillo,
+    /// The level has no movement table entity.
+    #[error("level has no Table entity")]
+    MissingTable,
+}
+
+/// The result of a single grave-action applied to a [GraveyardSimulation].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum TurnOutcome {
+    /// Willo is still alive and the level is incomplete.
+    Continue,
+    /// All goals have been met by gravestones.
+    LevelComplete,
+    /// Willo has been exorcised, so no more grave-actions can be performed.
+    WilloDied,
+}
+
+/// A gravestone in a [GraveyardSimulation].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct SimulatedGravestone {
+    /// The grave-action this gravestone is associated with.
+    pub grave_id: GraveId,
+    /// The position of the gravestone.
+    pub grid_coords: GridCoords,
+    /// Whether or not the gravestone has been exorcised.
+    pub volatile: Volatile,
+}
+
+/// The parts of a [GraveyardSimulation] that change from turn to turn.
+///
+/// Cheap to clone and hash, so it can be used to track visited states in a search.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub struct SimulationState {
+    willo: GridCoords,
+    willo_volatile: Volatile,
+    gravestones: Vec<SimulatedGravestone>,
+    exorcism_tiles: Vec<Volatile>,
+    pits: Vec<(GridCoords, Pit)>,
+    coffins: Vec<GridCoords>,
+}
+
+impl SimulationState {
+    /// Willo's position.
+    pub fn willo(&self) -> GridCoords {
+        self.willo
+    }
+
+    /// Returns `true` if Willo has been exorcised.
+    pub fn is_willo_dead(&self) -> bool {
+        !self.willo_volatile.is_solid()
+    }
+
+    /// All gravestones in the graveyard, including exorcised ones.
+    pub fn gravestones(&self) -> &[SimulatedGravestone] {
+        &self.gravestones
+    }
+
+    /// All pits in the graveyard, and whether or not they have been filled.
+    pub fn pits(&self) -> &[(GridCoords, Pit)] {
+        &self.pits
+    }
+
+    /// The positions of all coffins, which are the top-left corners of their footprints.
+    pub fn coffins(&self) -> &[GridCoords] {
+        &self.coffins
+    }
+}
+
+/// Identifies the occupant of a [CollisionMap] entry built by the simulation.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+enum Occupant {
+    Willo,
+    Gravestone(usize),
+    Coffin(usize),
+    Wall,
+    Pit,
+}
+
+/// Looks up the [Footprint]s of coffins, the only occupants that may occupy several cells.
+struct CoffinFootprints<'a>(&'a [Footprint]);
+
+impl Footprints<Occupant> for CoffinFootprints<'_> {
+    fn footprint(&self, occupant: Occupant) -> Option<&Footprint> {
+        match occupant {
+            Occupant::Coffin(i) => self.0.get(i),
+            _ => None,
+        }
+    }
+}
+
+/// ECS-free simulation of a graveyard puzzle.
+///
+/// See the [module-level docs](self) for more info.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct GraveyardSimulation {
+    size: IVec2,
+    walls: Vec<bool>,
+    terrain: Terrain,
+    conveyors: Vec<(GridCoords, Direction)>,
+    exorcism_tiles: Vec<GridCoords>,
+    coffin_footprints: Vec<Footprint>,
+    coffin_weights: Vec<Weight>,
+    willo_strength: Strength,
+    push_rule: PushRule,
+    goals: Vec<(GridCoords, Option<GraveId>)>,
+    exits: Vec<GridCoords>,
+    table: GridCoords,
+    table_layout: MovementTableLayout,
+    state: SimulationState,
+}
+
+impl GraveyardSimulation {
+    /// Constructs an empty graveyard with the given dimensions, Willo position, and movement table
+    /// position.
+    ///
+    /// The movement table has the default layout, see [GraveyardSimulation::set_table_layout].
+    pub fn new(size: IVec2, willo: GridCoords, table: GridCoords) -> GraveyardSimulation {
+        GraveyardSimulation {
+            size,
+            walls: vec![false; (size.x * size.y).max(0) as usize],
+            terrain: Terrain::new(size),
+            conveyors: Vec::new(),
+            exorcism_tiles: Vec::new(),
+            coffin_footprints: Vec::new(),
+            coffin_weights: Vec::new(),
+            willo_strength: Strength::default(),
+            push_rule: PushRule::default(),
+            goals: Vec::new(),
+            exits: Vec::new(),
+            table,
+            table_layout: MovementTableLayout::default(),
+            state: SimulationState {
+                willo,
+                willo_volatile: Volatile::Solid,
+                gravestones: Vec::new(),
+                exorcism_tiles: Vec::new(),
+                pits: Vec::new(),
+                coffins: Vec::new(),
+            },
+        }
+    }
+
+    /// Constructs the graveyard described by an LDtk level.
+    ///
+    /// The level must have its layer instances, so levels stored in external level files need to
+    /// be loaded from those files first.
+    pub fn from_level(level: &Level) -> Result<GraveyardSimulation, SimulationError> {
+        let layer_instances = level
+            .layer_instances
+            .as_ref()
+            .ok_or(SimulationError::MissingLayerInstances)?;
+
+        let sokoban_layer = layer_instances
+            .iter()
+            .find(|layer| layer.identifier == SOKOBAN_LAYER_IDENTIFIER)
+            .ok_or(SimulationError::MissingSokobanLayer)?;
+
+        let entity_instances = || {
+            layer_instances.iter().flat_map(|layer| {
+                layer.entity_instances.iter().map(|entity_instance| {
+                    (
+                        entity_instance,
+                        ldtk_grid_coords_to_grid_coords(entity_instance.grid, layer.c_hei),
+                    )
+                })
+            })
+        };
+
+        let find_entity = |identifier: &str| {
+            entity_instances().find(|(entity_instance, _)| entity_instance.identifier == identifier)
+        };
+
+        let (willo_instance, willo) = find_entity("Willo").ok_or(SimulationError::MissingWillo)?;
+        let (table_instance, table) = find_entity("Table").ok_or(SimulationError::MissingTable)?;
+
+        let mut simulation = GraveyardSimulation::new(
+            IVec2::new(sokoban_layer.c_wid, sokoban_layer.c_hei),
+            willo,
+            table,
+        );
+        simulation.set_table_layout(MovementTableLayout::from(table_instance));
+        simulation.set_willo_strength(willo_strength(willo_instance));
+        simulation.set_push_rule(level_push_rule(level));
+
+        for (i, value) in sokoban_layer.int_grid_csv.iter().enumerate() {
+            let ldtk_coords = IVec2::new(
+                i as i32 % sokoban_layer.c_wid,
+                i as i32 / sokoban_layer.c_wid,
+            );
+            let grid_coords = ldtk_grid_coords_to_grid_coords(ldtk_coords, sokoban_layer.c_hei);
+
+            if WALL_INT_GRID_VALUES.contains(value) {
+                simulation.add_wall(grid_coords);
+            } else if *value == EXORCISM_INT_GRID_VALUE {
+                simulation.add_exorcism_tile(grid_coords);
+            } else if *value == ICE_INT_GRID_VALUE {
+                simulation.add_ice(grid_coords);
+            } else if *value == PIT_INT_GRID_VALUE {
+                simulation.add_pit(grid_coords);
+            } else if let Some((_, direction)) = ONE_WAY_INT_GRID_VALUES
+                .iter()
+                .find(|(one_way_value, _)| one_way_value == value)
+            {
+                simulation.add_one_way(grid_coords, *direction);
+            } else if let Some((_, direction)) = CONVEYOR_INT_GRID_VALUES
+                .iter()
+                .find(|(conveyor_value, _)| conveyor_value == value)
+            {
+                simulation.add_conveyor(grid_coords, *direction);
+            }
+        }
+
+        let mut teleporters = Vec::new();
+
+        for (entity_instance, grid_coords) in entity_instances() {
+            match entity_instance.identifier.as_str() {
+                "Q" | "W" | "E" | "A" | "S" | "D" => {
+                    simulation.add_gravestone(GraveId::from(entity_instance), grid_coords)
+                }
+                "Goal" => match goal_grave_id(entity_instance) {
+                    Some(grave_id) => simulation.add_grave_goal(grid_coords, grave_id),
+                    None => simulation.add_goal(grid_coords),
+                },
+                "Exit" => simulation.add_exit(grid_coords),
+                "Coffin" => simulation.add_coffin(
+                    grid_coords,
+                    coffin_footprint(entity_instance),
+                    coffin_weight(entity_instance),
+                ),
+                "Teleporter" => {
+                    teleporters.push((IVec2::from(grid_coords), Teleporter::from(entity_instance)))
+                }
+                _ => (),
+            }
+        }
+
+        simulation.terrain.add_teleporters(
+            teleporters
+                .iter()
+                .map(|(grid_coords, teleporter)| (*grid_coords, teleporter)),
+        );
+
+        Ok(simulation)
+    }
+
+    /// Sets the shape of the movement table and the directions of its ranks and files.
+    pub fn set_table_layout(&mut self, layout: MovementTableLayout) {
+        self.table_layout = layout;
+    }
+
+    /// Sets how much a single push can move, [PushRule::Strength] by default.
+    pub fn set_push_rule(&mut self, push_rule: PushRule) {
+        self.push_rule = push_rule;
+    }
+
+    /// Sets the total weight Willo can push at once, which is unlimited by default.
+    pub fn set_willo_strength(&mut self, strength: Strength) {
+        self.willo_strength = strength;
+    }
+
+    /// Adds a static wall at the given position.
+    pub fn add_wall(&mut self, grid_coords: GridCoords) {
+        if let Some(index) = self.index(grid_coords) {
+            self.walls[index] = true;
+        }
+    }
+
+    /// Adds ice at the given position, which Willo and gravestones slide across.
+    pub fn add_ice(&mut self, grid_coords: GridCoords) {
+        self.terrain.add_slippery(IVec2::from(grid_coords));
+    }
+
+    /// Adds a one-way gate at the given position, which can only be entered in the given direction.
+    pub fn add_one_way(&mut self, grid_coords: GridCoords, direction: Direction) {
+        self.terrain
+            .add_one_way(IVec2::from(grid_coords), direction);
+    }
+
+    /// Adds a conveyor at the given position, which moves whatever stands on it one step in the
+    /// given direction after every grave-action.
+    pub fn add_conveyor(&mut self, grid_coords: GridCoords, direction: Direction) {
+        self.conveyors.push((grid_coords, direction));
+    }
+
+    /// Adds a pair of linked teleporters at the given positions.
+    pub fn add_teleporters(&mut self, a: GridCoords, b: GridCoords) {
+        self.terrain
+            .link_teleporters(IVec2::from(a), IVec2::from(b));
+    }
+
+    /// Adds an exorcism tile at the given position.
+    pub fn add_exorcism_tile(&mut self, grid_coords: GridCoords) {
+        self.exorcism_tiles.push(grid_coords);
+        self.state.exorcism_tiles.push(Volatile::Solid);
+    }
+
+    /// Adds an open pit at the given position.
+    pub fn add_pit(&mut self, grid_coords: GridCoords) {
+        self.state.pits.push((grid_coords, Pit::Open));
+    }
+
+    /// Adds a gravestone for the given grave-action at the given position.
+    pub fn add_gravestone(&mut self, grave_id: GraveId, grid_coords: GridCoords) {
+        self.state.gravestones.push(SimulatedGravestone {
+            grave_id,
+            grid_coords,
+            volatile: Volatile::Solid,
+        });
+    }
+
+    /// Adds a coffin at the given position, occupying the cells of the given footprint.
+    ///
+    /// Like for the [Footprint] component, the position is the top-left corner of the footprint.
+    pub fn add_coffin(&mut self, grid_coords: GridCoords, footprint: Footprint, weight: Weight) {
+        self.state.coffins.push(grid_coords);
+        self.coffin_footprints.push(footprint);
+        self.coffin_weights.push(weight);
+    }
+
+    /// Adds a goal at the given position, which any gravestone can satisfy.
+    pub fn add_goal(&mut self, grid_coords: GridCoords) {
+        self.goals.push((grid_coords, None));
+    }
+
+    /// Adds a goal at the given position, which only the given grave-action's gravestone can
+    /// satisfy.
+    pub fn add_grave_goal(&mut self, grid_coords: GridCoords, grave_id: GraveId) {
+        self.goals.push((grid_coords, Some(grave_id)));
+    }
+
+    /// Adds an exit gate at the given position.
+    ///
+    /// Once a graveyard has exit gates, Willo must also finish a grave-action on one of them after
+    /// every goal is met.
+    pub fn add_exit(&mut self, grid_coords: GridCoords) {
+        self.exits.push(grid_coords);
+    }
+
+    /// The current state of the graveyard.
+    pub fn state(&self) -> &SimulationState {
+        &self.state
+    }
+
+    /// Overwrites the current state of the graveyard, for example to rewind it.
+    pub fn set_state(&mut self, state: SimulationState) {
+        self.state = state;
+    }
+
+    /// The current state without details that can no longer affect the rest of the level.
+    ///
+    /// Exorcised gravestones never move or block anything again, so their position only matters
+    /// while they cover a goal that accepts them.
+    /// Elsewhere, they are moved to the origin, so states that only differ in where gravestones
+    /// were exorcised compare equal.
+    pub fn canonical_state(&self) -> SimulationState {
+        let mut state = self.state.clone();
+
+        for gravestone in state.gravestones.iter_mut() {
+            let covers_goal = self.goals.iter().any(|(goal, goal_grave_id)| {
+                *goal == gravestone.grid_coords
+                    && (goal_grave_id.is_none() || *goal_grave_id == Some(gravestone.grave_id))
+            });
+
+            if !gravestone.volatile.is_solid() && !covers_goal {
+                gravestone.grid_coords = GridCoords::default();
+            }
+        }
+
+        state
+    }
+
+    /// The dimensions of the graveyard.
+    pub fn size(&self) -> IVec2 {
+        self.size
+    }
+
+    /// Returns `true` if there is a static wall at the given position.
+    pub fn is_wall(&self, grid_coords: GridCoords) -> bool {
+        self.index(grid_coords)
+            .is_some_and(|index| self.walls[index])
+    }
+
+    /// The ice, teleporters and one-way gates of the graveyard.
+    pub fn terrain(&self) -> &Terrain {
+        &self.terrain
+    }
+
+    /// The positions and directions of all conveyors.
+    pub fn conveyors(&self) -> &[(GridCoords, Direction)] {
+        &self.conveyors
+    }
+
+    /// The positions of all goals, and the grave-action whose gravestone they require, if any.
+    pub fn goals(&self) -> &[(GridCoords, Option<GraveId>)] {
+        &self.goals
+    }
+
+    /// The positions of all exit gates.
+    pub fn exits(&self) -> &[GridCoords] {
+        &self.exits
+    }
+
+    /// The shape of the movement table and the directions of its ranks and files.
+    pub fn table_layout(&self) -> &MovementTableLayout {
+        &self.table_layout
+    }
+
+    /// All exorcism tiles in the graveyard, and whether or not they have been used up.
+    ///
+    /// Only whether they have been used up is part of the [SimulationState], which keeps the state
+    /// small in levels full of exorcism tiles.
+    pub fn exorcism_tiles(&self) -> impl Iterator<Item = (GridCoords, Volatile)> + '_ {
+        self.exorcism_tiles
+            .iter()
+            .copied()
+            .zip(self.state.exorcism_tiles.iter().copied())
+    }
+
+    /// The movement table as defined by the current placement of solid gravestones.
+    pub fn movement_table(&self) -> MovementTable {
+        MovementTable::from_gravestones(
+            self.table,
+            self.table_layout.clone(),
+            self.state
+                .gravestones
+                .iter()
+                .filter(|gravestone| gravestone.volatile.is_solid())
+                .map(|gravestone| (gravestone.grid_coords, gravestone.grave_id)),
+        )
+    }
+
+    /// Returns `true` if the graveyard has goals or exit gates, every goal is covered by a
+    /// gravestone it accepts, and Willo is alive on an exit gate if there are any.
+    ///
+    /// Like between grave-actions, Willo is assumed to have finished moving.
+    pub fn is_complete(&self) -> bool {
+        self.level_complete(true)
+    }
+
+    fn level_complete(&self, turn_finished: bool) -> bool {
+        let goals_met = self.goals.iter().map(|(goal, goal_grave_id)| {
+            meeting_gravestone(
+                *goal,
+                *goal_grave_id,
+                self.state
+                    .gravestones
+                    .iter()
+                    .map(|gravestone| ((), gravestone.grid_coords, gravestone.grave_id)),
+            )
+            .is_some()
+        });
+
+        let willo_finished_at =
+            (turn_finished && !self.state.is_willo_dead()).then_some(self.state.willo);
+
+        level_complete(goals_met, self.exits.iter().copied(), willo_finished_at)
+    }
+
+    /// Performs a full grave-action, consisting of Willo's rank move, file move, and conveyor move.
+    ///
+    /// Like in the game, the movement table is updated between the moves, and the turn ends early
+    /// if the level is completed or Willo dies after the rank move or file move.
+    /// Exit gates are only checked once the whole grave-action is finished.
+    pub fn apply(&mut self, grave_id: GraveId) -> TurnOutcome {
+        if self.state.is_willo_dead() {
+            return TurnOutcome::WilloDied;
+        }
+
+        let rank_directions: Vec<Direction> =
+            self.movement_table().rank_directions(grave_id).collect();
+        self.move_willo(&rank_directions);
+
+        match self.outcome(false) {
+            TurnOutcome::Continue => (),
+            outcome => return outcome,
+        }
+
+        let file_directions: Vec<Direction> =
+            self.movement_table().file_directions(grave_id).collect();
+        self.move_willo(&file_directions);
+
+        match self.outcome(false) {
+            TurnOutcome::Continue => (),
+            outcome => return outcome,
+        }
+
+        self.move_by_conveyors();
+
+        self.outcome(true)
+    }
+
+    fn outcome(&self, turn_finished: bool) -> TurnOutcome {
+        if self.level_complete(turn_finished) {
+            TurnOutcome::LevelComplete
+        } else if self.state.is_willo_dead() {
+            TurnOutcome::WilloDied
+        } else {
+            TurnOutcome::Continue
+        }
+    }
+
+    fn index(&self, grid_coords: GridCoords) -> Option<usize> {
+        if (0..self.size.x).contains(&grid_coords.x) && (0..self.size.y).contains(&grid_coords.y) {
+            Some((grid_coords.y * self.size.x + grid_coords.x) as usize)
+        } else {
+            None
+        }
+    }
+
+    fn collision_map(&self) -> CollisionMap<Occupant> {
+        let mut collision_map: CollisionMap<Occupant> =
+            vec![vec![None; self.size.x as usize]; self.size.y as usize];
+
+        let mut insert = |grid_coords: GridCoords, entry: (Occupant, SokobanBlock)| {
+            if let Some(row) = collision_map.get_mut(grid_coords.y as usize) {
+                if let Some(cell) = row.get_mut(grid_coords.x as usize) {
+                    *cell = Some(entry);
+                }
+            }
+        };
+
+        for (index, _) in self.walls.iter().enumerate().filter(|(_, wall)| **wall) {
+            let grid_coords =
+                GridCoords::new(index as i32 % self.size.x, index as i32 / self.size.x);
+            insert(grid_coords, (Occupant::Wall, SokobanBlock::Static));
+        }
+
+        for (grid_coords, pit) in self.state.pits.iter() {
+            if *pit == Pit::Open {
+                insert(*grid_coords, (Occupant::Pit, SokobanBlock::Hole));
+            }
+        }
+
+        for (i, gravestone) in self.state.gravestones.iter().enumerate() {
+            if gravestone.volatile.is_solid() {
+                insert(
+                    gravestone.grid_coords,
+                    (Occupant::Gravestone(i), SokobanBlock::Dynamic),
+                );
+            }
+        }
+
+        for (i, (grid_coords, footprint)) in self
+            .state
+            .coffins
+            .iter()
+            .zip(self.coffin_footprints.iter())
+            .enumerate()
+        {
+            for cell in footprint.cells_at(IVec2::from(*grid_coords)) {
+                insert(
+                    GridCoords::from(cell),
+                    (Occupant::Coffin(i), SokobanBlock::Dynamic),
+                );
+            }
+        }
+
+        insert(self.state.willo, (Occupant::Willo, SokobanBlock::Dynamic));
+
+        collision_map
+    }
+
+    fn occupant_grid_coords(&self, occupant: Occupant) -> Option<GridCoords> {
+        match occupant {
+            Occupant::Willo => Some(self.state.willo),
+            Occupant::Gravestone(i) => {
+                let gravestone = &self.state.gravestones[i];
+                gravestone
+                    .volatile
+                    .is_solid()
+                    .then_some(gravestone.grid_coords)
+            }
+            Occupant::Coffin(i) => Some(self.state.coffins[i]),
+            Occupant::Wall | Occupant::Pit => None,
+        }
+    }
+
+    /// A cell of the collision map occupied by the occupant, which multi-cell coffins may not have
+    /// at their position.
+    fn occupant_cell(&self, occupant: Occupant) -> Option<IVec2> {
+        let grid_coords = IVec2::from(self.occupant_grid_coords(occupant)?);
+
+        match occupant {
+            Occupant::Coffin(i) => Some(grid_coords + self.coffin_footprints[i].cells()[0]),
+            _ => Some(grid_coords),
+        }
+    }
+
+    /// The [Strength] of an occupant, only Willo has one.
+    fn occupant_strength(&self, occupant: Occupant) -> Option<Strength> {
+        match occupant {
+            Occupant::Willo => Some(self.willo_strength),
+            _ => None,
+        }
+    }
+
+    /// The [Weight] of an occupant, only coffins may weigh more than the default.
+    fn occupant_weight(&self, occupant: Occupant) -> Weight {
+        match occupant {
+            Occupant::Coffin(i) => self.coffin_weights[i],
+            _ => Weight::default(),
+        }
+    }
+
+    /// Moves Willo in each of the given directions, then resolves pits and exorcisms.
+    fn move_willo(&mut self, directions: &[Direction]) {
+        let moves: Vec<_> = directions
+            .iter()
+            .map(|direction| (Occupant::Willo, *direction))
+            .collect();
+
+        self.move_occupants(&moves);
+    }
+
+    /// Moves Willo, gravestones and coffins standing on conveyors, then resolves pits and
+    /// exorcisms.
+    fn move_by_conveyors(&mut self) {
+        let moves = conveyor_moves(
+            std::iter::once(Occupant::Willo)
+                .chain((0..self.state.gravestones.len()).map(Occupant::Gravestone))
+                .chain((0..self.state.coffins.len()).map(Occupant::Coffin))
+                .filter_map(|occupant| Some((occupant, self.occupant_grid_coords(occupant)?))),
+            |grid_coords| {
+                self.conveyors
+                    .iter()
+                    .find(|(conveyor_grid_coords, _)| *conveyor_grid_coords == grid_coords)
+                    .map(|(_, direction)| *direction)
+            },
+        );
+
+        self.move_occupants(&moves);
+    }
+
+    /// Moves each occupant in its direction, in order, then resolves pits and exorcisms.
+    fn move_occupants(&mut self, moves: &[(Occupant, Direction)]) {
+        let mut collision_map = self.collision_map();
+        let mut moved = Vec::new();
+
+        for (mover, direction) in moves {
+            let Some(mover_cell) = self.occupant_cell(*mover) else {
+                continue;
+            };
+
+            let pushed = limited_move_collision_map_entry(
+                &mut collision_map,
+                &self.terrain,
+                &CoffinFootprints(&self.coffin_footprints),
+                mover_cell,
+                *direction,
+                DIAGONAL_SQUEEZE,
+                self.push_rule,
+                self.occupant_strength(*mover),
+                |occupant| self.occupant_weight(occupant),
+            )
+            .ok();
+
+            for (occupant, path) in pushed.into_iter().flatten() {
+                let grid_coords = match occupant {
+                    Occupant::Willo => &mut self.state.willo,
+                    Occupant::Gravestone(i) => &mut self.state.gravestones[i].grid_coords,
+                    Occupant::Coffin(i) => &mut self.state.coffins[i],
+                    Occupant::Wall | Occupant::Pit => {
+                        unreachable!("walls and pits are static and cannot be pushed")
+                    }
+                };
+                *grid_coords = GridCoords::from(
+                    *path
+                        .last()
+                        .expect("moved occupant should have moved at least once"),
+                );
+
+                if !moved.contains(&occupant) {
+                    moved.push(occupant);
+                }
+            }
+        }
+
+        let SimulationState {
+            willo,
+            willo_volatile,
+            gravestones,
+            exorcism_tiles,
+            pits,
+            ..
+        } = &mut self.state;
+
+        // gravestones pushed into pits are sublimated before they can exorcise anything
+        let mut gravestone_volatiles: Vec<_> = gravestones
+            .iter_mut()
+            .map(|gravestone| (gravestone.grid_coords, &mut gravestone.volatile))
+            .collect();
+
+        fill_pits(
+            pits.iter_mut()
+                .map(|(grid_coords, pit)| (*grid_coords, pit)),
+            &mut gravestone_volatiles,
+        );
+
+        let mut moved_volatiles = Vec::new();
+        let mut stationary_volatiles = Vec::new();
+
+        if moved.contains(&Occupant::Willo) {
+            moved_volatiles.push((*willo, willo_volatile));
+        } else {
+            stationary_volatiles.push((*willo, willo_volatile));
+        }
+
+        for (i, gravestone) in gravestones.iter_mut().enumerate() {
+            if moved.contains(&Occupant::Gravestone(i)) {
+                moved_volatiles.push((gravestone.grid_coords, &mut gravestone.volatile));
+            } else {
+                stationary_volatiles.push((gravestone.grid_coords, &mut gravestone.volatile));
+            }
+        }
+
+        for (grid_coords, volatile) in self.exorcism_tiles.iter().zip(exorcism_tiles.iter_mut()) {
+            stationary_volatiles.push((*grid_coords, volatile));
+        }
+
+        sublimate_collisions(&mut moved_volatiles, &mut stationary_volatiles);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// 10x6 graveyard with a 5x6 play area on the left and the movement table on the right.
+    ///
+    /// The table's rank `r` and file `f` is at `(6 + f, 4 - r)`.
+    /// The play area is walled off from the table.
+    pub(crate) fn simulation_setup(willo: GridCoords) -> GraveyardSimulation {
+        let mut simulation =
+            GraveyardSimulation::new(IVec2::new(10, 6), willo, GridCoords::new(5, 5));
+
+        for y in 0..6 {
+            simulation.add_wall(GridCoords::new(5, y));
+        }
+
+        simulation
+    }
+
+    #[test]
+    fn grave_action_moves_by_rank_then_file() {
+        let mut simulation = simulation_setup(GridCoords::new(2, 2));
+
+        // rank: up, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 4));
+
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(3, 3));
+
+        // gravestones that aren't on the table don't move Willo
+        assert_eq!(simulation.apply(GraveId::South), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(3, 3));
+    }
+
+    #[test]
+    fn push_gravestone_onto_goal_completes_level() {
+        let mut simulation = simulation_setup(GridCoords::new(0, 2));
+
+        // rank: right, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        simulation.add_gravestone(GraveId::West, GridCoords::new(1, 2));
+        simulation.add_goal(GridCoords::new(3, 2));
+
+        assert!(!simulation.is_complete());
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::LevelComplete);
+        assert_eq!(simulation.state().willo(), GridCoords::new(2, 2));
+    }
+
+    #[test]
+    fn grave_goal_requires_its_gravestone() {
+        let mut simulation = simulation_setup(GridCoords::new(0, 2));
+
+        // rank: right, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        simulation.add_gravestone(GraveId::West, GridCoords::new(1, 2));
+        simulation.add_grave_goal(GridCoords::new(3, 2), GraveId::South);
+
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(
+            simulation.state().gravestones()[1].grid_coords,
+            GridCoords::new(3, 2)
+        );
+        assert!(!simulation.is_complete());
+
+        let mut simulation = simulation_setup(GridCoords::new(0, 2));
+
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        simulation.add_gravestone(GraveId::West, GridCoords::new(1, 2));
+        simulation.add_grave_goal(GridCoords::new(3, 2), GraveId::West);
+
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::LevelComplete);
+    }
+
+    #[test]
+    fn exit_requires_willo_after_goals_are_met() {
+        let exit_setup = |goal: GridCoords, exit: GridCoords| {
+            let mut simulation = simulation_setup(GridCoords::new(0, 2));
+
+            // rank: right, file: right
+            simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+            simulation.add_gravestone(GraveId::West, GridCoords::new(1, 2));
+            simulation.add_goal(goal);
+            simulation.add_exit(exit);
+            simulation
+        };
+
+        let mut simulation = exit_setup(GridCoords::new(3, 2), GridCoords::new(2, 2));
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::LevelComplete);
+
+        // the goal is met, but Willo isn't on the exit
+        let mut simulation = exit_setup(GridCoords::new(3, 2), GridCoords::new(1, 2));
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert!(!simulation.is_complete());
+
+        // Willo is on the exit with the goal met after the rank move, but not after the file move
+        let mut simulation = exit_setup(GridCoords::new(2, 2), GridCoords::new(1, 2));
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+    }
+
+    #[test]
+    fn table_layout_defines_directions() {
+        let mut simulation = simulation_setup(GridCoords::new(2, 2));
+
+        // 3x2 table with a no-op rank and a repeated file direction
+        simulation.set_table_layout(MovementTableLayout {
+            ranks: vec![Some(Direction::Up), None, Some(Direction::Down)],
+            files: vec![Some(Direction::Right), Some(Direction::Right)],
+        });
+
+        // rank: none, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(7, 3));
+        // rank: down, file: right
+        simulation.add_gravestone(GraveId::South, GridCoords::new(6, 2));
+        // outside of the 3x2 table
+        simulation.add_gravestone(GraveId::West, GridCoords::new(8, 4));
+
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(3, 2));
+
+        assert_eq!(simulation.apply(GraveId::South), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(4, 1));
+
+        assert_eq!(simulation.apply(GraveId::West), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(4, 1));
+    }
+
+    #[test]
+    fn gravestones_slide_across_ice() {
+        let mut simulation = simulation_setup(GridCoords::new(0, 2));
+
+        // rank: right, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        simulation.add_gravestone(GraveId::West, GridCoords::new(1, 2));
+        simulation.add_ice(GridCoords::new(2, 2));
+        simulation.add_ice(GridCoords::new(3, 2));
+        simulation.add_wall(GridCoords::new(5, 2));
+
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(
+            simulation.state().gravestones()[1].grid_coords,
+            GridCoords::new(4, 2)
+        );
+        assert_eq!(simulation.state().willo(), GridCoords::new(3, 2));
+    }
+
+    #[test]
+    fn gravestone_fills_pit() {
+        let mut simulation = simulation_setup(GridCoords::new(0, 2));
+
+        // rank: right, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        simulation.add_gravestone(GraveId::West, GridCoords::new(2, 2));
+        simulation.add_pit(GridCoords::new(3, 2));
+        simulation.add_pit(GridCoords::new(5, 2));
+
+        // Willo pushes the gravestone into the pit, then walks over the filled pit
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(2, 2));
+        assert_eq!(
+            simulation.state().pits(),
+            &[
+                (GridCoords::new(3, 2), Pit::Filled),
+                (GridCoords::new(5, 2), Pit::Open)
+            ]
+        );
+        assert!(!simulation.state().gravestones()[1].volatile.is_solid());
+
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(4, 2));
+        assert!(!simulation.state().is_willo_dead());
+
+        // open pits block Willo
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(4, 2));
+    }
+
+    #[test]
+    fn teleporters_relocate_willo_and_gravestones() {
+        let mut simulation = simulation_setup(GridCoords::new(0, 2));
+
+        // rank: right, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        simulation.add_gravestone(GraveId::West, GridCoords::new(0, 0));
+        simulation.add_teleporters(GridCoords::new(1, 2), GridCoords::new(0, 0));
+
+        // Willo enters the teleporter and arrives on its partner, pushing the gravestone there out
+        // in the same direction
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(1, 0));
+        assert_eq!(
+            simulation.state().gravestones()[1].grid_coords,
+            GridCoords::new(2, 0)
+        );
+    }
+
+    #[test]
+    fn conveyors_move_after_the_file_move() {
+        let mut simulation = simulation_setup(GridCoords::new(0, 2));
+
+        // rank: right, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        simulation.add_gravestone(GraveId::West, GridCoords::new(2, 3));
+        simulation.add_conveyor(GridCoords::new(2, 2), Direction::Up);
+        simulation.add_conveyor(GridCoords::new(2, 3), Direction::Up);
+
+        // Willo and the gravestone ahead of them on the belt move up together
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(2, 3));
+        assert_eq!(
+            simulation.state().gravestones()[1].grid_coords,
+            GridCoords::new(2, 4)
+        );
+    }
+
+    #[test]
+    fn one_way_gates_only_allow_entry_in_their_direction() {
+        let mut simulation = simulation_setup(GridCoords::new(0, 2));
+
+        // rank: right, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        // rank: left, file: left
+        simulation.add_gravestone(GraveId::West, GridCoords::new(7, 3));
+        simulation.add_one_way(GridCoords::new(1, 2), Direction::Right);
+
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(2, 2));
+
+        // Willo can't go back through the gate
+        assert_eq!(simulation.apply(GraveId::West), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(2, 2));
+    }
+
+    #[test]
+    fn coffins_are_pushed_as_a_whole() {
+        let mut simulation = simulation_setup(GridCoords::new(0, 2));
+
+        // rank: right, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        simulation.add_coffin(
+            GridCoords::new(1, 3),
+            Footprint::rectangle(IVec2::new(1, 2)),
+            Weight::default(),
+        );
+        simulation.add_wall(GridCoords::new(3, 3));
+
+        // Willo pushes the coffin by its bottom cell, until its top cell hits the wall
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(1, 2));
+        assert_eq!(simulation.state().coffins(), &[GridCoords::new(2, 3)]);
+    }
+
+    #[test]
+    fn pushes_are_limited_by_the_push_rule() {
+        let push_rule_setup = |push_rule: PushRule, strength: Strength| {
+            let mut simulation = simulation_setup(GridCoords::new(0, 2));
+
+            // rank: right, file: right
+            simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+            simulation.add_gravestone(GraveId::West, GridCoords::new(1, 2));
+            simulation.add_coffin(GridCoords::new(3, 2), Footprint::default(), Weight(2));
+            simulation.set_push_rule(push_rule);
+            simulation.set_willo_strength(strength);
+            simulation
+        };
+
+        // Willo can push the gravestone alone, but not along with the heavy coffin
+        let mut simulation = push_rule_setup(PushRule::Strength, Strength(2));
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(1, 2));
+        assert_eq!(simulation.state().coffins(), &[GridCoords::new(3, 2)]);
+
+        let mut simulation = push_rule_setup(PushRule::Strength, Strength(3));
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(2, 2));
+        assert_eq!(simulation.state().coffins(), &[GridCoords::new(4, 2)]);
+
+        // classic pushes ignore strength
+        let mut simulation = push_rule_setup(PushRule::SingleBlock, Strength::default());
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(1, 2));
+    }
+
+    #[test]
+    fn walls_block_pushes() {
+        let mut simulation = simulation_setup(GridCoords::new(0, 2));
+
+        // rank: right, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        simulation.add_gravestone(GraveId::West, GridCoords::new(1, 2));
+        simulation.add_wall(GridCoords::new(2, 2));
+
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(0, 2));
+        assert_eq!(
+            simulation.state().gravestones()[1].grid_coords,
+            GridCoords::new(1, 2)
+        );
+    }
+
+    #[test]
+    fn exorcism_tile_kills_willo_and_cancels_file_move() {
+        let mut simulation = simulation_setup(GridCoords::new(2, 2));
+
+        // rank: up, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 4));
+        simulation.add_exorcism_tile(GridCoords::new(2, 3));
+
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::WilloDied);
+        assert_eq!(simulation.state().willo(), GridCoords::new(2, 3));
+        assert!(simulation.state().is_willo_dead());
+
+        // dead Willo can't perform grave-actions
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::WilloDied);
+        assert_eq!(simulation.state().willo(), GridCoords::new(2, 3));
+    }
+
+    #[test]
+    fn gravestone_pushed_onto_exorcism_tile_is_exorcised() {
+        let mut simulation = simulation_setup(GridCoords::new(0, 2));
+
+        // rank: right, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        simulation.add_gravestone(GraveId::West, GridCoords::new(1, 2));
+        simulation.add_exorcism_tile(GridCoords::new(2, 2));
+
+        // Willo walks over the exorcised gravestone and the used-up tile safely
+        assert_eq!(simulation.apply(GraveId::North), TurnOutcome::Continue);
+        assert_eq!(simulation.state().willo(), GridCoords::new(2, 2));
+        assert_eq!(
+            simulation.state().gravestones()[1].volatile,
+            Volatile::Sublimated
+        );
+        assert_eq!(
+            simulation.exorcism_tiles().collect::<Vec<_>>(),
+            [(GridCoords::new(2, 2), Volatile::Sublimated)]
+        );
+    }
+}
@@ -0,0 +1,563 @@
+//! A* search for solutions of graveyard puzzles.
+//!
+//! Built on [GraveyardSimulation], so solutions follow the same rules as the game.
+//!
+//! States are ordered by the number of grave-actions performed so far, plus a lower bound of the
+//! grave-actions still needed (see [Heuristic]).
+//! That bound can be weighted to find longer solutions faster, see [solve].
+use crate::{
+    graveyard::{
+        gravestone::GraveId,
+        simulation::{GraveyardSimulation, SimulationState, TurnOutcome},
+    },
+    sokoban::Direction,
+};
+use bevy::prelude::*;
+use bevy::utils::hashbrown::{hash_map::RawEntryMut, HashMap};
+use bevy_ecs_ldtk::prelude::*;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{hash_map::RandomState, BinaryHeap, VecDeque},
+    hash::BuildHasher,
+};
+
+/// The result of searching a graveyard for a solution.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Solution {
+    /// A sequence of grave-actions that completes the level.
+    Solved(Vec<GraveId>),
+    /// Every reachable state was searched, and none of them complete the level.
+    Unsolvable,
+    /// The search visited the maximum number of states without finding a solution.
+    GaveUp,
+}
+
+/// A visited state of the search.
+struct Node {
+    state: SimulationState,
+    /// The index of the state this one was reached from, and the grave-action that reached it.
+    parent: Option<(usize, GraveId)>,
+    /// The fewest grave-actions this state has been reached in so far.
+    cost: u32,
+    /// Whether or not the state has been searched already.
+    expanded: bool,
+    /// Whether or not the level is complete in this state.
+    complete: bool,
+}
+
+/// Every visited state, along with an index of them by their full contents.
+///
+/// The index only stores positions in the list of nodes, so each state is only stored once.
+#[derive(Default)]
+struct Visited {
+    nodes: Vec<Node>,
+    index: HashMap<usize, (), ()>,
+    hasher: RandomState,
+}
+
+impl Visited {
+    /// The position of the node storing the given state, if it has been visited.
+    fn find(&self, hash: u64, state: &SimulationState) -> Option<usize> {
+        self.index
+            .raw_entry()
+            .from_hash(hash, |i| self.nodes[*i].state == *state)
+            .map(|(i, _)| *i)
+    }
+
+    /// Stores a node whose state hasn't been visited before, returning its position.
+    fn insert(&mut self, hash: u64, node: Node) -> usize {
+        let Visited {
+            nodes,
+            index,
+            hasher,
+        } = self;
+
+        nodes.push(node);
+        let i = nodes.len() - 1;
+
+        // the new state isn't in the index yet, so nothing can match it
+        if let RawEntryMut::Vacant(entry) = index.raw_entry_mut().from_hash(hash, |_| false) {
+            entry.insert_with_hasher(hash, i, (), |i| hasher.hash_one(&nodes[*i].state));
+        }
+
+        i
+    }
+}
+
+/// A state waiting to be searched, ordered so that a [BinaryHeap] pops the one with the lowest
+/// estimated total first, preferring those with the most grave-actions performed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct Open {
+    estimate: u32,
+    cost: u32,
+    index: usize,
+}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (Reverse(self.estimate), self.cost, Reverse(self.index)).cmp(&(
+            Reverse(other.estimate),
+            other.cost,
+            Reverse(other.index),
+        ))
+    }
+}
+
+/// Searches for a sequence of grave-actions that completes the level.
+///
+/// With a `weight` of 1, this is an A* search, and the solution is one of the shortest.
+/// Larger weights trust the [Heuristic] more, which usually finds a solution in far fewer states,
+/// but it may be up to `weight` times longer than the shortest one.
+///
+/// States that Willo dies in, or that can no longer reach a goal, are not searched any further.
+/// States are compared in full, after dropping details that can't affect the rest of the level
+/// (see [GraveyardSimulation::canonical_state]), so no state is ever mistaken for another.
+/// The search gives up after visiting `max_states` unique states.
+pub fn solve(simulation: &GraveyardSimulation, weight: u32, max_states: usize) -> Solution {
+    if simulation.is_complete() {
+        return Solution::Solved(Vec::new());
+    }
+
+    let heuristic = Heuristic::new(simulation);
+    let mut simulation = simulation.clone();
+
+    let start = simulation.canonical_state();
+    let Some(estimate) = heuristic.estimate(&start) else {
+        return Solution::Unsolvable;
+    };
+
+    let mut visited = Visited::default();
+    let start_hash = visited.hasher.hash_one(&start);
+    visited.insert(
+        start_hash,
+        Node {
+            state: start,
+            parent: None,
+            cost: 0,
+            expanded: false,
+            complete: false,
+        },
+    );
+
+    let mut open = BinaryHeap::from([Open {
+        estimate: weight * estimate,
+        cost: 0,
+        index: 0,
+    }]);
+
+    while let Some(Open { cost, index, .. }) = open.pop() {
+        let node = &mut visited.nodes[index];
+        if node.expanded || node.cost != cost {
+            // stale entry, the state has been reached in fewer grave-actions since
+            continue;
+        }
+        node.expanded = true;
+
+        if node.complete {
+            let mut grave_ids = Vec::new();
+            let mut parent = node.parent;
+            while let Some((index, grave_id)) = parent {
+                grave_ids.push(grave_id);
+                parent = visited.nodes[index].parent;
+            }
+            grave_ids.reverse();
+
+            return Solution::Solved(grave_ids);
+        }
+
+        for grave_id in GraveId::ALL {
+            simulation.set_state(visited.nodes[index].state.clone());
+
+            let complete = match simulation.apply(grave_id) {
+                TurnOutcome::Continue => false,
+                TurnOutcome::LevelComplete => true,
+                TurnOutcome::WilloDied => continue,
+            };
+
+            let next_state = simulation.canonical_state();
+            let next_cost = cost + 1;
+
+            let estimate = if complete {
+                0
+            } else {
+                match heuristic.estimate(&next_state) {
+                    Some(estimate) => estimate,
+                    None => continue,
+                }
+            };
+
+            let hash = visited.hasher.hash_one(&next_state);
+            let next_index = match visited.find(hash, &next_state) {
+                Some(next_index) => {
+                    let next_node = &mut visited.nodes[next_index];
+                    if next_node.expanded || next_node.cost <= next_cost {
+                        continue;
+                    }
+
+                    next_node.cost = next_cost;
+                    next_node.parent = Some((index, grave_id));
+                    next_index
+                }
+                None => {
+                    if visited.nodes.len() >= max_states {
+                        return Solution::GaveUp;
+                    }
+
+                    visited.insert(
+                        hash,
+                        Node {
+                            state: next_state,
+                            parent: Some((index, grave_id)),
+                            cost: next_cost,
+                            expanded: false,
+                            complete,
+                        },
+                    )
+                }
+            };
+
+            open.push(Open {
+                estimate: next_cost + weight * estimate,
+                cost: next_cost,
+                index: next_index,
+            });
+        }
+    }
+
+    Solution::Unsolvable
+}
+
+/// Lower bound of the grave-actions needed to complete a level from a given state.
+///
+/// Pushes are relaxed so that only static walls block gravestones, and teleporters are free to
+/// pass through.
+/// Every goal needs a gravestone it accepts, and Willo needs to reach an exit gate if there are
+/// any, so the bound is the largest of their relaxed distances, divided by the furthest a block
+/// can move in one grave-action.
+///
+/// The bound never overestimates, and it drops by at most 1 per grave-action, so A* searches with
+/// it find the shortest solutions.
+pub struct Heuristic {
+    size: IVec2,
+    /// For every goal, the grave-action it requires, if any, and the relaxed distance from every
+    /// cell to it.
+    goals: Vec<(GridCoords, Option<GraveId>, Vec<Option<u32>>)>,
+    /// The relaxed distance from every cell to the nearest exit gate, if there are any.
+    exits: Option<Vec<Option<u32>>>,
+    /// The furthest a block can move in one grave-action.
+    reach: u32,
+}
+
+impl Heuristic {
+    /// Precomputes the relaxed distances and reach of the given graveyard.
+    pub fn new(simulation: &GraveyardSimulation) -> Heuristic {
+        let diagonal = simulation
+            .table_layout()
+            .ranks
+            .iter()
+            .chain(simulation.table_layout().files.iter())
+            .flatten()
+            .any(|direction| direction.orthogonal_components().is_some());
+
+        let directions: &[Direction] = if diagonal {
+            &[
+                Direction::Up,
+                Direction::Left,
+                Direction::Down,
+                Direction::Right,
+                Direction::UpLeft,
+                Direction::UpRight,
+                Direction::DownLeft,
+                Direction::DownRight,
+            ]
+        } else {
+            &[
+                Direction::Up,
+                Direction::Left,
+                Direction::Down,
+                Direction::Right,
+            ]
+        };
+
+        let goals = simulation
+            .goals()
+            .iter()
+            .map(|(goal, grave_id)| {
+                (
+                    *goal,
+                    *grave_id,
+                    relaxed_distances(simulation, directions, [*goal]),
+                )
+            })
+            .collect();
+
+        let exits = (!simulation.exits().is_empty())
+            .then(|| relaxed_distances(simulation, directions, simulation.exits().iter().copied()));
+
+        // Willo moves once per rank and once per file containing the grave-action's gravestones.
+        let most_gravestones = GraveId::ALL
+            .iter()
+            .map(|grave_id| {
+                simulation
+                    .state()
+                    .gravestones()
+                    .iter()
+                    .filter(|gravestone| gravestone.grave_id == *grave_id)
+                    .count() as u32
+            })
+            .max()
+            .unwrap_or_default();
+        let conveyor_moves = u32::from(!simulation.conveyors().is_empty());
+        let moves = 2 * most_gravestones + conveyor_moves;
+
+        Heuristic {
+            size: simulation.size(),
+            goals,
+            exits,
+            reach: moves * (1 + longest_slide(simulation, directions)),
+        }
+    }
+
+    /// The lower bound of grave-actions needed to complete the level from the given state.
+    ///
+    /// Returns `None` if the level can't be completed from the state.
+    pub fn estimate(&self, state: &SimulationState) -> Option<u32> {
+        let mut distance = 0;
+
+        for (goal, goal_grave_id, distances) in self.goals.iter() {
+            let goal_distance = state
+                .gravestones()
+                .iter()
+                .filter(|gravestone| {
+                    goal_grave_id.is_none() || *goal_grave_id == Some(gravestone.grave_id)
+                })
+                .filter_map(|gravestone| {
+                    if gravestone.volatile.is_solid() {
+                        self.distance(distances, gravestone.grid_coords)
+                    } else {
+                        // exorcised gravestones stay where they are
+                        (gravestone.grid_coords == *goal).then_some(0)
+                    }
+                })
+                .min()?;
+
+            distance = distance.max(goal_distance);
+        }
+
+        if let Some(distances) = &self.exits {
+            distance = distance.max(self.distance(distances, state.willo())?);
+        }
+
+        if distance == 0 {
+            Some(0)
+        } else if self.reach == 0 {
+            None
+        } else {
+            Some(distance.div_ceil(self.reach))
+        }
+    }
+
+    fn distance(&self, distances: &[Option<u32>], grid_coords: GridCoords) -> Option<u32> {
+        cell_index(self.size, grid_coords).and_then(|index| distances[index])
+    }
+}
+
+fn cell_index(size: IVec2, grid_coords: GridCoords) -> Option<usize> {
+    if (0..size.x).contains(&grid_coords.x) && (0..size.y).contains(&grid_coords.y) {
+        Some((grid_coords.y * size.x + grid_coords.x) as usize)
+    } else {
+        None
+    }
+}
+
+/// Distances from every cell to the nearest of the targets, moving in the given directions.
+///
+/// Only static walls block, and stepping from a teleporter to its partner is free.
+fn relaxed_distances(
+    simulation: &GraveyardSimulation,
+    directions: &[Direction],
+    targets: impl IntoIterator<Item = GridCoords>,
+) -> Vec<Option<u32>> {
+    let size = simulation.size();
+    let mut distances = vec![None; (size.x * size.y).max(0) as usize];
+    let mut queue = VecDeque::new();
+
+    for target in targets {
+        if let Some(index) = cell_index(size, target) {
+            distances[index] = Some(0);
+            queue.push_back((target, 0));
+        }
+    }
+
+    while let Some((grid_coords, distance)) = queue.pop_front() {
+        let index = cell_index(size, grid_coords).expect("queued cells should be in the graveyard");
+        if distances[index] != Some(distance) {
+            continue;
+        }
+
+        let teleport = simulation
+            .terrain()
+            .teleporters
+            .get(grid_coords.y as usize)
+            .and_then(|row| row.get(grid_coords.x as usize))
+            .copied()
+            .flatten()
+            .map(|partner| (GridCoords::from(partner), distance));
+
+        let steps = directions.iter().map(|direction| {
+            (
+                GridCoords::from(IVec2::from(grid_coords) + IVec2::from(*direction)),
+                distance + 1,
+            )
+        });
+
+        for (next, next_distance) in teleport.into_iter().chain(steps) {
+            let Some(next_index) = cell_index(size, next) else {
+                continue;
+            };
+
+            if simulation.is_wall(next)
+                || distances[next_index].is_some_and(|known| known <= next_distance)
+            {
+                continue;
+            }
+
+            distances[next_index] = Some(next_distance);
+            if next_distance == distance {
+                queue.push_front((next, next_distance));
+            } else {
+                queue.push_back((next, next_distance));
+            }
+        }
+    }
+
+    distances
+}
+
+/// The most ice cells a block can slide across after a single move.
+///
+/// Slides may continue through teleporters, so every ice cell counts once there are any.
+fn longest_slide(simulation: &GraveyardSimulation, directions: &[Direction]) -> u32 {
+    let terrain = simulation.terrain();
+    let is_ice = |coords: IVec2| {
+        terrain
+            .slippery
+            .get(usize::try_from(coords.y).ok()?)?
+            .get(usize::try_from(coords.x).ok()?)
+            .copied()
+    };
+
+    let has_teleporters = terrain.teleporters.iter().flatten().any(Option::is_some);
+    if has_teleporters {
+        return terrain
+            .slippery
+            .iter()
+            .flatten()
+            .filter(|ice| **ice)
+            .count() as u32;
+    }
+
+    let size = simulation.size();
+    let mut longest = 0;
+
+    for y in 0..size.y {
+        for x in 0..size.x {
+            for direction in directions {
+                let mut coords = IVec2::new(x, y);
+                let mut run = 0;
+                while is_ice(coords) == Some(true) {
+                    run += 1;
+                    coords += IVec2::from(*direction);
+                }
+
+                longest = longest.max(run);
+            }
+        }
+    }
+
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graveyard::simulation::tests::simulation_setup;
+
+    #[test]
+    fn finds_shortest_solution() {
+        let mut simulation = simulation_setup(GridCoords::new(0, 0));
+
+        // rank: right, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        // rank: up, file: up
+        simulation.add_gravestone(GraveId::West, GridCoords::new(6, 4));
+        simulation.add_gravestone(GraveId::South, GridCoords::new(1, 2));
+        simulation.add_goal(GridCoords::new(4, 2));
+
+        assert_eq!(
+            solve(&simulation, 1, 10_000),
+            Solution::Solved(vec![GraveId::West, GraveId::North, GraveId::North])
+        );
+    }
+
+    #[test]
+    fn reports_unsolvable_levels() {
+        let mut simulation = simulation_setup(GridCoords::new(0, 0));
+
+        // rank: right, file: right
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        simulation.add_gravestone(GraveId::South, GridCoords::new(1, 2));
+        simulation.add_goal(GridCoords::new(4, 2));
+
+        assert_eq!(solve(&simulation, 1, 10_000), Solution::Unsolvable);
+    }
+
+    #[test]
+    fn estimate_never_exceeds_the_shortest_solution() {
+        let mut simulation = simulation_setup(GridCoords::new(0, 0));
+
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        simulation.add_gravestone(GraveId::West, GridCoords::new(6, 4));
+        simulation.add_gravestone(GraveId::South, GridCoords::new(1, 2));
+        simulation.add_goal(GridCoords::new(4, 2));
+
+        let heuristic = Heuristic::new(&simulation);
+        let mut state = simulation.canonical_state();
+
+        for (grave_id, remaining) in [(GraveId::West, 3), (GraveId::North, 2), (GraveId::North, 1)]
+        {
+            assert!(heuristic.estimate(&state).unwrap() <= remaining);
+
+            simulation.set_state(state);
+            simulation.apply(grave_id);
+            state = simulation.canonical_state();
+        }
+
+        assert_eq!(heuristic.estimate(&state), Some(0));
+    }
+
+    #[test]
+    fn unreachable_goals_are_not_searched() {
+        let mut simulation = simulation_setup(GridCoords::new(0, 0));
+
+        simulation.add_gravestone(GraveId::North, GridCoords::new(9, 1));
+        simulation.add_gravestone(GraveId::West, GridCoords::new(1, 2));
+        simulation.add_goal(GridCoords::new(4, 2));
+        // the goal is walled in
+        for (x, y) in [(3, 2), (4, 1), (4, 3)] {
+            simulation.add_wall(GridCoords::new(x, y));
+        }
+
+        assert_eq!(
+            Heuristic::new(&simulation).estimate(simulation.state()),
+            None
+        );
+        assert_eq!(solve(&simulation, 1, 10_000), Solution::Unsolvable);
+    }
+}
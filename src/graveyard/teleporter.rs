@@ -0,0 +1,39 @@
+//! Plugin providing spawning logic for teleporters, which relocate Willo and gravestones.
+//!
+//! Teleporters are linked in pairs by their "Partner" LDtk entity reference field.
+//! See [Teleporter] for how they move sokoban blocks.
+use crate::sokoban::Teleporter;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+/// Plugin providing spawning logic for teleporters, which relocate Willo and gravestones.
+pub struct TeleporterPlugin;
+
+impl Plugin for TeleporterPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<TeleporterBundle>("Teleporter");
+    }
+}
+
+impl From<&EntityInstance> for Teleporter {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Teleporter {
+            iid: entity_instance.iid.clone(),
+            partner: entity_instance
+                .get_maybe_entity_ref_field("Partner")
+                .ok()
+                .and_then(|partner| partner.as_ref())
+                .map(|partner| partner.entity_iid.clone()),
+        }
+    }
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+struct TeleporterBundle {
+    #[grid_coords]
+    grid_coords: GridCoords,
+    #[from_entity_instance]
+    teleporter: Teleporter,
+    #[sprite_sheet_bundle]
+    sprite_sheet_bundle: SpriteSheetBundle,
+}
@@ -4,6 +4,7 @@
 //! Once they come into contact with another Volatile entity - they are both "Sublimated".
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
+use std::ops::DerefMut;
 
 use crate::{
     history::{FlushHistoryCommands, HistoryPlugin},
@@ -41,7 +42,7 @@ impl Plugin for VolatilePlugin {
 ///
 /// If two volatile solids share the same [`GridCoords`] space, they both are sublimated.
 /// What this means for a particular entity should be defined elsewhere.
-#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Component)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Component)]
 pub enum Volatile {
     /// The entity is still a volatile solid.
     #[default]
@@ -66,24 +67,42 @@ impl Volatile {
 ///
 /// Obtains a separate query for *moving* volatiles and *all* volatiles.
 /// This is so it can split moving volatiles and stationary volatiles into separate collections.
-/// This allows us to limit our collision detection to checking moved-volatiles against all-volatiles,
-/// rather than all-volatiles against all-volatiles.
+/// See [sublimate_collisions] for the collision logic itself.
 fn sublimation(
     moved_volatile_entities: Query<(), (With<Volatile>, Changed<GridCoords>)>,
     mut all_volatiles: Query<(Entity, &GridCoords, &mut Volatile)>,
 ) {
     // Split volatiles into moved and stationary collections.
-    let (mut moved_volatiles, mut stationary_volatiles): (Vec<_>, Vec<_>) = all_volatiles
-        .iter_mut()
-        .partition(|(entity, ..)| moved_volatile_entities.contains(*entity));
+    let mut moved_volatiles = Vec::new();
+    let mut stationary_volatiles = Vec::new();
+
+    for (entity, grid_coords, volatile) in all_volatiles.iter_mut() {
+        if moved_volatile_entities.contains(entity) {
+            moved_volatiles.push((*grid_coords, volatile));
+        } else {
+            stationary_volatiles.push((*grid_coords, volatile));
+        }
+    }
+
+    sublimate_collisions(&mut moved_volatiles, &mut stationary_volatiles);
+}
 
+/// Sublimates solid [`Volatile`]s that share [`GridCoords`] with another solid.
+///
+/// Volatiles are split into *moved* and *stationary* collections.
+/// This allows us to limit our collision detection to checking moved-volatiles against all-volatiles,
+/// rather than all-volatiles against all-volatiles.
+pub fn sublimate_collisions<V: DerefMut<Target = Volatile>>(
+    moved_volatiles: &mut [(GridCoords, V)],
+    stationary_volatiles: &mut [(GridCoords, V)],
+) {
     // Check for collisions between moved volatiles.
-    for index in 0..moved_volatiles.len() - 1 {
-        if let [(_, grid_coords_a, volatile_a), remaining_moved_volatiles @ ..] =
+    for index in 0..moved_volatiles.len().saturating_sub(1) {
+        if let [(grid_coords_a, volatile_a), remaining_moved_volatiles @ ..] =
             &mut moved_volatiles[index..]
         {
             if volatile_a.is_solid() {
-                for (_, grid_coords_b, volatile_b) in remaining_moved_volatiles.iter_mut() {
+                for (grid_coords_b, volatile_b) in remaining_moved_volatiles.iter_mut() {
                     if volatile_b.is_solid() && grid_coords_a == grid_coords_b {
                         volatile_a.sublimate();
                         volatile_b.sublimate();
@@ -94,9 +113,9 @@ fn sublimation(
     }
 
     // Check for collisions between moved volatiles and stationary volatiles.
-    for (_, grid_coords_a, volatile_a) in moved_volatiles.iter_mut() {
+    for (grid_coords_a, volatile_a) in moved_volatiles.iter_mut() {
         if volatile_a.is_solid() {
-            for (_, grid_coords_b, volatile_b) in stationary_volatiles.iter_mut() {
+            for (grid_coords_b, volatile_b) in stationary_volatiles.iter_mut() {
                 if volatile_b.is_solid() && grid_coords_a == grid_coords_b {
                     volatile_a.sublimate();
                     volatile_b.sublimate();
@@ -1,18 +1,41 @@
-//! Plugin providing spawning logic for static walls such as bushes and fences.
-use crate::sokoban::SokobanBlock;
+//! Plugin providing spawning logic for static walls such as bushes and fences, one-way gates, and
+//! ice.
+use crate::sokoban::{Direction, OneWay, Slippery, SokobanBlock};
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 
-/// Plugin providing spawning logic for static walls such as bushes and fences.
+/// Plugin providing spawning logic for static walls such as bushes and fences, one-way gates, and
+/// ice.
 pub struct WallPlugin;
 
-const WALL_INT_GRID_VALUES: &[i32] = &[1, 3, 4];
+/// IntGrid values of the LDtk "IntGrid" layer that are spawned as static walls.
+pub const WALL_INT_GRID_VALUES: &[i32] = &[1, 3, 4];
+
+/// IntGrid value of the LDtk "IntGrid" layer that is spawned as ice.
+///
+/// Willo and gravestones that end a move on ice keep sliding until they hit something.
+pub const ICE_INT_GRID_VALUE: i32 = 6;
+
+/// IntGrid values of the LDtk "IntGrid" layer that are spawned as one-way gates, and the only
+/// directions they can be entered in.
+pub const ONE_WAY_INT_GRID_VALUES: [(i32, Direction); 4] = [
+    (8, Direction::Up),
+    (9, Direction::Left),
+    (10, Direction::Down),
+    (11, Direction::Right),
+];
 
 impl Plugin for WallPlugin {
     fn build(&self, app: &mut App) {
         WALL_INT_GRID_VALUES.iter().for_each(|value| {
             app.register_ldtk_int_cell::<WallBundle>(*value);
         });
+
+        ONE_WAY_INT_GRID_VALUES.iter().for_each(|(value, _)| {
+            app.register_ldtk_int_cell::<OneWayBundle>(*value);
+        });
+
+        app.register_ldtk_int_cell::<IceBundle>(ICE_INT_GRID_VALUE);
     }
 }
 
@@ -22,6 +45,17 @@ struct WallBundle {
     sokoban_block: SokobanBlock,
 }
 
+#[derive(Clone, Bundle, LdtkIntCell)]
+struct OneWayBundle {
+    #[from_int_grid_cell]
+    one_way: OneWay,
+}
+
+#[derive(Clone, Default, Bundle, LdtkIntCell)]
+struct IceBundle {
+    slippery: Slippery,
+}
+
 impl From<IntGridCell> for SokobanBlock {
     fn from(cell: IntGridCell) -> SokobanBlock {
         if WALL_INT_GRID_VALUES.contains(&cell.value) {
@@ -31,3 +65,13 @@ impl From<IntGridCell> for SokobanBlock {
         }
     }
 }
+
+impl From<IntGridCell> for OneWay {
+    fn from(cell: IntGridCell) -> OneWay {
+        ONE_WAY_INT_GRID_VALUES
+            .iter()
+            .find(|(value, _)| *value == cell.value)
+            .map(|(_, direction)| OneWay(*direction))
+            .expect("tried to give non-gate cell a OneWay")
+    }
+}
@@ -2,9 +2,13 @@
 use crate::{
     animation::{FromComponentAnimator, SpriteSheetAnimation},
     from_component::FromComponentSet,
-    graveyard::{exorcism::ExorcismEvent, gravestone::GraveId, volatile::Volatile},
+    graveyard::{
+        exorcism::ExorcismEvent, gravestone::GraveId, move_counter::MoveCount, volatile::Volatile,
+    },
     history::{History, HistoryCommands, HistoryPlugin},
-    sokoban::{Direction, PushEvent, PushTracker, SokobanBlock, SokobanSets},
+    sokoban::{
+        Direction, MoveBlocked, PushEvent, PushTracker, SokobanBlock, SokobanSets, Strength,
+    },
     AssetHolder, GameState, UNIT_LENGTH,
 };
 use bevy::prelude::*;
@@ -37,6 +41,10 @@ impl Plugin for WilloPlugin {
                     .run_if(not(in_state(GameState::AssetLoading)))
                     .run_if(on_event::<PushEvent>())
                     .before(FromComponentSet),
+                bump_sugar
+                    .run_if(not(in_state(GameState::AssetLoading)))
+                    .run_if(on_event::<MoveBlocked>())
+                    .before(FromComponentSet),
                 play_exorcism_animaton
                     .run_if(not(in_state(GameState::AssetLoading)))
                     .run_if(on_event::<ExorcismEvent>()),
@@ -71,6 +79,10 @@ pub enum WilloState {
     ///
     /// This move is defined by the file of the gravestone on the movement table.
     FileMove(GraveId),
+    /// Willo has finished a grave action, and conveyors are moving the blocks standing on them.
+    ///
+    /// See [crate::graveyard::conveyor].
+    ConveyorMove,
 }
 
 /// Component enumerating the possible states of Willo's animation.
@@ -80,6 +92,8 @@ pub enum WilloAnimationState {
     Idle(Direction),
     /// Willo is pushing a gravestione in a particular direction.
     Push(Direction),
+    /// Willo bumped into something they can't move in a particular direction.
+    Bump(Direction),
     /// Willo is dying.
     Dying,
     /// Willo is invisible (post-death).
@@ -97,7 +111,9 @@ impl Iterator for WilloAnimationState {
     fn next(&mut self) -> Option<Self::Item> {
         Some(match self {
             WilloAnimationState::Dying | WilloAnimationState::None => WilloAnimationState::None,
-            WilloAnimationState::Push(d) => WilloAnimationState::Idle(*d),
+            WilloAnimationState::Push(d) | WilloAnimationState::Bump(d) => {
+                WilloAnimationState::Idle(*d)
+            }
             _ => WilloAnimationState::Idle(Direction::Down),
         })
     }
@@ -108,15 +124,16 @@ impl From<WilloAnimationState> for SpriteSheetAnimation {
         use Direction::*;
         use WilloAnimationState::*;
 
+        // diagonal directions face their horizontal component
         let indices = match state {
-            Push(Up) => 1..2,
-            Push(Down) => 11..12,
-            Push(Left) => 21..22,
-            Push(Right) => 31..32,
+            Push(Up) | Bump(Up) => 1..2,
+            Push(Down) | Bump(Down) => 11..12,
+            Push(Left | UpLeft | DownLeft) | Bump(Left | UpLeft | DownLeft) => 21..22,
+            Push(Right | UpRight | DownRight) | Bump(Right | UpRight | DownRight) => 31..32,
             Idle(Up) => 40..47,
             Idle(Down) => 50..57,
-            Idle(Left) => 60..67,
-            Idle(Right) => 70..77,
+            Idle(Left | UpLeft | DownLeft) => 60..67,
+            Idle(Right | UpRight | DownRight) => 70..77,
             Dying => 80..105,
             None => 3..4,
         };
@@ -153,6 +170,8 @@ struct WilloBundle {
     #[with(SokobanBlock::new_dynamic)]
     sokoban_block: SokobanBlock,
     push_tracker: PushTracker,
+    #[with(willo_strength)]
+    strength: Strength,
     willo_state: WilloState,
     movement_timer: MovementTimer,
     #[sprite_sheet_bundle]
@@ -160,6 +179,21 @@ struct WilloBundle {
     willo_animation_state: WilloAnimationState,
     volatile: Volatile,
     volatile_history: History<Volatile>,
+    move_count: MoveCount,
+    move_count_history: History<MoveCount>,
+}
+
+/// Willo's [Strength], as defined by the optional "Strength" field of their LDtk entity.
+///
+/// Without the field, Willo can push any weight.
+pub fn willo_strength(entity_instance: &EntityInstance) -> Strength {
+    entity_instance
+        .get_maybe_int_field("Strength")
+        .ok()
+        .copied()
+        .flatten()
+        .and_then(|strength| u32::try_from(strength).ok())
+        .map_or(Strength::default(), Strength)
 }
 
 fn push_sugar(
@@ -181,6 +215,28 @@ fn push_sugar(
     }
 }
 
+fn bump_sugar(
+    mut commands: Commands,
+    mut move_blocked_events: EventReader<MoveBlocked>,
+    mut willo_query: Query<(Entity, &mut WilloAnimationState)>,
+    sfx: Res<AssetHolder>,
+) {
+    let Ok((willo_entity, mut animation_state)) = willo_query.get_single_mut() else {
+        return;
+    };
+
+    for MoveBlocked { direction, .. } in move_blocked_events
+        .read()
+        .filter(|MoveBlocked { entity, .. }| *entity == willo_entity)
+    {
+        commands.spawn(AudioBundle {
+            source: sfx.thud_sound.clone(),
+            settings: PlaybackSettings::DESPAWN,
+        });
+        *animation_state = WilloAnimationState::Bump(*direction);
+    }
+}
+
 fn push_translation(
     mut commands: Commands,
     willo_query: Query<
@@ -191,7 +247,12 @@ fn push_translation(
     if let Ok((entity, &grid_coords, transform, animation_state)) = willo_query.get_single() {
         let xy = grid_coords_to_translation(grid_coords, IVec2::splat(UNIT_LENGTH))
             + match animation_state {
-                WilloAnimationState::Push(direction) => IVec2::from(*direction).as_vec2() * 5.,
+                WilloAnimationState::Push(direction) => {
+                    IVec2::from(*direction).as_vec2().normalize() * 5.
+                }
+                WilloAnimationState::Bump(direction) => {
+                    IVec2::from(*direction).as_vec2().normalize() * 3.
+                }
                 _ => Vec2::splat(0.),
             };
 
@@ -213,7 +274,7 @@ fn history_sugar(
 ) {
     for command in history_commands.read() {
         match command {
-            HistoryCommands::Rewind | HistoryCommands::Reset => {
+            HistoryCommands::Rewind | HistoryCommands::Redo | HistoryCommands::Reset => {
                 *willo_query.single_mut() = WilloAnimationState::Idle(Direction::Down);
                 commands.spawn(AudioBundle {
                     source: sfx.undo_sound.clone(),
@@ -1,6 +1,9 @@
 //! Plugin that tracks history, rewinds, and resets gamestate for arbitrary components.
-use bevy::prelude::*;
-use std::marker::PhantomData;
+//!
+//! Entities that are spawned or despawned during gameplay can be tracked too, see
+//! [LifecycleHistoryPlugin].
+use bevy::{ecs::system::EntityCommands, hierarchy::despawn_with_children_recursive, prelude::*};
+use std::{any::TypeId, collections::HashMap, marker::PhantomData};
 
 /// Plugin that tracks history, rewinds, and resets gamestate for arbitrary components.
 pub struct HistoryPlugin<C: Component + Clone, S: States> {
@@ -26,23 +29,84 @@ impl<C: Component + Clone, S: States> Plugin for HistoryPlugin<C, S> {
             Update,
             flush_history_commands::<C>
                 .run_if(in_state(self.state.clone()))
-                .in_set(FlushHistoryCommands),
+                .in_set(FlushHistoryCommands)
+                .in_set(FlushComponentHistories),
         );
     }
 }
 
+/// Plugin that despawns and respawns entities according to history commands.
+///
+/// Only entities spawned or despawned via [LifecycleHistoryCommandsExt] are tracked.
+/// Despawned entities are stored as snapshots of their reflected components, and respawned from
+/// them.
+/// Every component of a tracked entity should be registered in the [AppTypeRegistry] with
+/// `#[reflect(Component)]`, components that aren't are reported with a warning and left out of the
+/// snapshot.
+/// This includes the [History] of components tracked by a [HistoryPlugin], which the plugin doesn't
+/// register itself.
+/// Descendants are snapshotted and respawned along with their entity, so the hierarchy is restored.
+///
+/// Respawned entities get new [Entity] ids, so components referring to a despawned entity by its
+/// old id are left dangling. Use the entity's [LifecycleId] to refer to it across respawns.
+///
+/// The [LifecycleHistory] is not cleared automatically, this should be done between levels.
+pub struct LifecycleHistoryPlugin<S: States> {
+    state: S,
+}
+
+impl<S: States> LifecycleHistoryPlugin<S> {
+    /// Constructor for the plugin.
+    ///
+    /// Allows the user to specify a particular state to run the plugin in.
+    pub fn run_in_state(state: S) -> Self {
+        LifecycleHistoryPlugin { state }
+    }
+}
+
+impl<S: States> Plugin for LifecycleHistoryPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HistoryCommands>()
+            .init_resource::<LifecycleHistory>()
+            .add_systems(
+                Update,
+                (
+                    // respawns are applied immediately so their components can be rewound
+                    (flush_lifecycle_history, apply_deferred)
+                        .chain()
+                        .run_if(in_state(self.state.clone()))
+                        .in_set(FlushHistoryCommands)
+                        .before(FlushComponentHistories),
+                    despawn_rewound_entities
+                        .run_if(in_state(self.state.clone()))
+                        .in_set(FlushHistoryCommands)
+                        .after(FlushComponentHistories),
+                ),
+            );
+    }
+}
+
 /// Event that can be fired by the user to command the plugin to perform various history tasks.
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Event)]
 pub enum HistoryCommands {
     /// Record the current state of all tracked components to their histories.
+    ///
+    /// This also clears any rewound states, so they can no longer be redone.
     Record,
     /// Update the current state of all tracked components with the previous state and remove it
     /// from the history.
+    ///
+    /// The current state is kept so that the rewind can be undone via [HistoryCommands::Redo].
     Rewind,
+    /// Update the current state of all tracked components with the most recently rewound state.
+    ///
+    /// The current state is recorded to the history so that the redo can be rewound again.
+    Redo,
     /// Update the current state of all tracked components to the first state in the history.
     ///
     /// Note: This also records the current state to the history before updating it.
     /// This allows the act of resetting the history to be rewound via [HistoryCommands::Rewind].
+    /// Like [HistoryCommands::Record], this clears any rewound states.
     Reset,
 }
 
@@ -50,9 +114,367 @@ pub enum HistoryCommands {
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, SystemSet)]
 pub struct FlushHistoryCommands;
 
+/// System label for the systems that handle history commands for components.
+///
+/// Respawned entities need to exist before their components are rewound, while despawned
+/// entities need their components to be rewound before they are snapshotted.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, SystemSet)]
+struct FlushComponentHistories;
+
 /// Component that stores the history of another component generically.
-#[derive(Clone, PartialEq, Eq, Debug, Default, Component, Deref, DerefMut)]
-pub struct History<C: Component + Clone>(Vec<C>);
+#[derive(Clone, PartialEq, Eq, Debug, Component, Reflect)]
+#[reflect(Component)]
+pub struct History<C: Component + Clone> {
+    past: Vec<C>,
+    future: Vec<C>,
+}
+
+impl<C: Component + Clone> Default for History<C> {
+    fn default() -> Self {
+        History {
+            past: Vec::new(),
+            future: Vec::new(),
+        }
+    }
+}
+
+impl<C: Component + Clone> History<C> {
+    /// Previously recorded states, ordered from oldest to newest.
+    pub fn past(&self) -> &[C] {
+        &self.past
+    }
+
+    /// Rewound states that can be redone, ordered from newest to oldest.
+    pub fn future(&self) -> &[C] {
+        &self.future
+    }
+}
+
+/// Component storing the id of an entity tracked by the [LifecycleHistory].
+///
+/// The id stays the same when the entity is respawned, unlike its `Entity`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Component)]
+pub struct LifecycleId(u64);
+
+/// A spawn or despawn tracked by the [LifecycleHistory].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+enum LifecycleChange {
+    Spawned(LifecycleId),
+    Despawned(LifecycleId),
+}
+
+impl LifecycleChange {
+    fn id(self) -> LifecycleId {
+        match self {
+            LifecycleChange::Spawned(id) | LifecycleChange::Despawned(id) => id,
+        }
+    }
+
+    fn inverse(self) -> LifecycleChange {
+        match self {
+            LifecycleChange::Spawned(id) => LifecycleChange::Despawned(id),
+            LifecycleChange::Despawned(id) => LifecycleChange::Spawned(id),
+        }
+    }
+}
+
+/// The changes undoing the given changes, in the order they should be performed.
+fn inverse(changes: &[LifecycleChange]) -> Vec<LifecycleChange> {
+    changes
+        .iter()
+        .rev()
+        .map(|change| change.inverse())
+        .collect()
+}
+
+/// The reflected components, parent, and descendants of a despawned entity.
+struct EntitySnapshot {
+    parent: Option<Entity>,
+    components: Vec<(ReflectComponent, Box<dyn Reflect>)>,
+    children: Vec<EntitySnapshot>,
+}
+
+/// Resource that stores the history of spawns and despawns, see [LifecycleHistoryPlugin].
+///
+/// Like component [History], changes are grouped by [HistoryCommands::Record].
+#[derive(Default, Resource)]
+pub struct LifecycleHistory {
+    next_id: u64,
+    past: Vec<Vec<LifecycleChange>>,
+    present: Vec<LifecycleChange>,
+    future: Vec<Vec<LifecycleChange>>,
+    snapshots: HashMap<LifecycleId, EntitySnapshot>,
+    pending_despawns: Vec<LifecycleId>,
+}
+
+impl LifecycleHistory {
+    /// Forgets every tracked spawn and despawn, along with the snapshots of despawned entities.
+    pub fn clear(&mut self) {
+        *self = LifecycleHistory {
+            next_id: self.next_id,
+            ..default()
+        };
+    }
+
+    fn new_id(&mut self) -> LifecycleId {
+        self.next_id += 1;
+        LifecycleId(self.next_id)
+    }
+
+    /// Updates the history according to the command, returning the changes to perform.
+    fn apply(&mut self, command: HistoryCommands) -> Vec<LifecycleChange> {
+        match command {
+            HistoryCommands::Record => {
+                self.past.push(std::mem::take(&mut self.present));
+                self.future.clear();
+                Vec::new()
+            }
+            HistoryCommands::Rewind => match self.past.pop() {
+                Some(prev_changes) => {
+                    let changes = inverse(&std::mem::replace(&mut self.present, prev_changes));
+                    self.future.push(changes.clone());
+                    changes
+                }
+                None => Vec::new(),
+            },
+            HistoryCommands::Redo => match self.future.pop() {
+                Some(next_changes) => {
+                    let changes = inverse(&next_changes);
+                    self.past
+                        .push(std::mem::replace(&mut self.present, changes.clone()));
+                    changes
+                }
+                None => Vec::new(),
+            },
+            HistoryCommands::Reset => {
+                if self.past.is_empty() {
+                    return Vec::new();
+                }
+
+                // changes made before the first record are part of the initial state
+                let changes: Vec<LifecycleChange> = std::iter::once(&self.present)
+                    .chain(self.past[1..].iter().rev())
+                    .flat_map(|changes| inverse(changes))
+                    .collect();
+
+                self.past
+                    .push(std::mem::replace(&mut self.present, changes.clone()));
+                self.future.clear();
+
+                changes
+            }
+        }
+    }
+}
+
+/// Removes changes that cancel each other out.
+///
+/// Entities alternate between being spawned and despawned, so only the last change of each entity
+/// matters, and only if it changed an odd number of times.
+fn net_changes(changes: impl IntoIterator<Item = LifecycleChange>) -> Vec<LifecycleChange> {
+    let mut net_changes: Vec<LifecycleChange> = Vec::new();
+
+    for change in changes {
+        match net_changes
+            .iter()
+            .position(|net_change| net_change.id() == change.id())
+        {
+            Some(index) => {
+                net_changes.remove(index);
+            }
+            None => net_changes.push(change),
+        }
+    }
+
+    net_changes
+}
+
+fn snapshot_and_despawn(world: &mut World, entity: Entity, id: LifecycleId) {
+    let snapshot = snapshot(world, entity);
+
+    world
+        .resource_mut::<LifecycleHistory>()
+        .snapshots
+        .insert(id, snapshot);
+
+    despawn_with_children_recursive(world, entity);
+}
+
+/// Components restored by the [LifecycleHistoryPlugin] itself rather than from snapshots.
+fn is_lifecycle_component(type_id: TypeId) -> bool {
+    [
+        TypeId::of::<Parent>(),
+        TypeId::of::<Children>(),
+        TypeId::of::<LifecycleId>(),
+    ]
+    .contains(&type_id)
+}
+
+fn snapshot(world: &World, entity: Entity) -> EntitySnapshot {
+    let entity_ref = world.entity(entity);
+    let type_registry = world.resource::<AppTypeRegistry>().read();
+    let mut components = Vec::new();
+
+    for info in entity_ref
+        .archetype()
+        .components()
+        .filter_map(|component_id| world.components().get_info(component_id))
+    {
+        if info.type_id().is_some_and(is_lifecycle_component) {
+            continue;
+        }
+
+        let reflected = info
+            .type_id()
+            .and_then(|type_id| type_registry.get_type_data::<ReflectComponent>(type_id))
+            .and_then(|reflect_component| {
+                Some((
+                    reflect_component.clone(),
+                    reflect_component.reflect(entity_ref)?.clone_value(),
+                ))
+            });
+
+        match reflected {
+            Some(component) => components.push(component),
+            None => warn!(
+                "component {} is not registered with #[reflect(Component)], it won't be respawned",
+                info.name()
+            ),
+        }
+    }
+
+    drop(type_registry);
+
+    EntitySnapshot {
+        parent: entity_ref.get::<Parent>().map(Parent::get),
+        components,
+        children: entity_ref
+            .get::<Children>()
+            .into_iter()
+            .flatten()
+            .map(|child| snapshot(world, *child))
+            .collect(),
+    }
+}
+
+fn respawn(world: &mut World, id: LifecycleId) {
+    let Some(snapshot) = world
+        .resource_mut::<LifecycleHistory>()
+        .snapshots
+        .remove(&id)
+    else {
+        warn!("attempted to respawn entity {id:?}, but it has no snapshot");
+        return;
+    };
+
+    let parent = snapshot
+        .parent
+        .filter(|parent| world.get_entity(*parent).is_some());
+
+    let entity = spawn_snapshot(world, snapshot, parent);
+    world.entity_mut(entity).insert(id);
+}
+
+/// Spawns the entity and descendants stored in the snapshot, returning the entity.
+fn spawn_snapshot(world: &mut World, snapshot: EntitySnapshot, parent: Option<Entity>) -> Entity {
+    let mut entity = world.spawn_empty();
+
+    for (reflect_component, component) in snapshot.components {
+        reflect_component.insert(&mut entity, &*component);
+    }
+
+    let entity = entity.id();
+
+    if let Some(parent) = parent {
+        world.entity_mut(parent).add_child(entity);
+    }
+
+    for child in snapshot.children {
+        spawn_snapshot(world, child, Some(entity));
+    }
+
+    entity
+}
+
+/// Extension trait for [EntityCommands] that tracks spawns and despawns in the [LifecycleHistory].
+pub trait LifecycleHistoryCommandsExt {
+    /// Records that this entity was spawned, so that rewinding past this point despawns it.
+    fn spawned_with_history(&mut self) -> &mut Self;
+
+    /// Despawns this entity and its descendants, recording a snapshot of it so that rewinding past
+    /// this point respawns it.
+    fn despawn_with_history(&mut self);
+}
+
+impl LifecycleHistoryCommandsExt for EntityCommands<'_, '_, '_> {
+    fn spawned_with_history(&mut self) -> &mut Self {
+        self.add(|mut entity: EntityWorldMut| {
+            let id = entity.world_scope(|world| {
+                let mut history = world.resource_mut::<LifecycleHistory>();
+                let id = history.new_id();
+                history.present.push(LifecycleChange::Spawned(id));
+                id
+            });
+
+            entity.insert(id);
+        })
+    }
+
+    fn despawn_with_history(&mut self) {
+        self.add(|entity: EntityWorldMut| {
+            let lifecycle_id = entity.get::<LifecycleId>().copied();
+            let entity_id = entity.id();
+            // the entity is despawned, so the world can't be borrowed via a scope
+            let world = entity.into_world_mut();
+
+            let id = match lifecycle_id {
+                Some(id) => id,
+                None => world.resource_mut::<LifecycleHistory>().new_id(),
+            };
+
+            world
+                .resource_mut::<LifecycleHistory>()
+                .present
+                .push(LifecycleChange::Despawned(id));
+
+            snapshot_and_despawn(world, entity_id, id);
+        });
+    }
+}
+
+/// Respawns entities according to history commands, and queues despawns for
+/// [despawn_rewound_entities].
+fn flush_lifecycle_history(
+    mut commands: Commands,
+    mut history: ResMut<LifecycleHistory>,
+    mut history_commands: EventReader<HistoryCommands>,
+) {
+    let changes: Vec<LifecycleChange> = history_commands
+        .read()
+        .flat_map(|command| history.apply(*command))
+        .collect();
+
+    for change in net_changes(changes) {
+        match change {
+            LifecycleChange::Spawned(id) => {
+                commands.add(move |world: &mut World| respawn(world, id))
+            }
+            LifecycleChange::Despawned(id) => history.pending_despawns.push(id),
+        }
+    }
+}
+
+fn despawn_rewound_entities(
+    mut commands: Commands,
+    mut history: ResMut<LifecycleHistory>,
+    lifecycle_query: Query<(Entity, &LifecycleId)>,
+) {
+    for id in history.pending_despawns.drain(..) {
+        if let Some((entity, _)) = lifecycle_query.iter().find(|(_, other)| **other == id) {
+            commands.add(move |world: &mut World| snapshot_and_despawn(world, entity, id));
+        }
+    }
+}
 
 fn flush_history_commands<C: Component + Clone>(
     mut history_query: Query<(&mut History<C>, &mut C)>,
@@ -62,23 +484,34 @@ fn flush_history_commands<C: Component + Clone>(
         match command {
             HistoryCommands::Record => {
                 for (mut history, component) in history_query.iter_mut() {
-                    history.push(component.clone());
+                    history.past.push(component.clone());
+                    history.future.clear();
                 }
             }
             HistoryCommands::Rewind => {
                 for (mut history, mut component) in history_query.iter_mut() {
-                    if let Some(prev_state) = history.pop() {
+                    if let Some(prev_state) = history.past.pop() {
+                        history.future.push(component.clone());
                         *component = prev_state;
                     }
                 }
             }
+            HistoryCommands::Redo => {
+                for (mut history, mut component) in history_query.iter_mut() {
+                    if let Some(next_state) = history.future.pop() {
+                        history.past.push(component.clone());
+                        *component = next_state;
+                    }
+                }
+            }
             HistoryCommands::Reset => {
                 for (mut history, mut component) in history_query.iter_mut() {
-                    if let Some(first) = history.first() {
+                    if let Some(first) = history.past.first() {
                         // Cloning is done before pushing to avoid borrow check issues
                         let first = first.clone();
 
-                        history.push(component.clone());
+                        history.past.push(component.clone());
+                        history.future.clear();
 
                         // Updating to a clone of the first item instead of rewinding the entire
                         // list allows us to rewind the act of resetting.
@@ -89,3 +522,243 @@ fn flush_history_commands<C: Component + Clone>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::SystemState;
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component, Reflect)]
+    #[reflect(Component)]
+    struct Value(i32);
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component, Reflect)]
+    #[reflect(Component)]
+    struct Label(u8);
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+    struct Unreflected;
+
+    #[derive(Clone, Eq, PartialEq, Debug, Default, Hash, States)]
+    enum State {
+        #[default]
+        Only,
+    }
+
+    fn app_setup() -> (App, Entity) {
+        let mut app = App::new();
+
+        app.add_state::<State>()
+            .add_plugins(HistoryPlugin::<Value, _>::run_in_state(State::Only));
+
+        let entity = app
+            .world
+            .spawn((Value(0), History::<Value>::default()))
+            .id();
+
+        (app, entity)
+    }
+
+    fn send_command(app: &mut App, command: HistoryCommands) {
+        let mut system_state: SystemState<EventWriter<HistoryCommands>> =
+            SystemState::new(&mut app.world);
+        system_state.get_mut(&mut app.world).send(command);
+
+        app.update();
+    }
+
+    fn set_value(app: &mut App, entity: Entity, value: i32) {
+        send_command(app, HistoryCommands::Record);
+        app.world.get_mut::<Value>(entity).unwrap().0 = value;
+    }
+
+    fn value(app: &App, entity: Entity) -> i32 {
+        app.world.get::<Value>(entity).unwrap().0
+    }
+
+    fn lifecycle_app_setup() -> (App, Entity) {
+        let (mut app, entity) = app_setup();
+
+        app.add_plugins(LifecycleHistoryPlugin::run_in_state(State::Only))
+            .register_type::<Value>()
+            .register_type::<History<Value>>();
+
+        (app, entity)
+    }
+
+    fn with_commands(app: &mut App, f: impl FnOnce(&mut Commands)) {
+        let mut system_state: SystemState<Commands> = SystemState::new(&mut app.world);
+        f(&mut system_state.get_mut(&mut app.world));
+        system_state.apply(&mut app.world);
+    }
+
+    fn values(app: &mut App) -> Vec<i32> {
+        let mut values: Vec<i32> = app
+            .world
+            .query::<&Value>()
+            .iter(&app.world)
+            .map(|value| value.0)
+            .collect();
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn rewind_then_redo_restores_state() {
+        let (mut app, entity) = app_setup();
+
+        set_value(&mut app, entity, 1);
+        set_value(&mut app, entity, 2);
+
+        send_command(&mut app, HistoryCommands::Rewind);
+        send_command(&mut app, HistoryCommands::Rewind);
+        assert_eq!(value(&app, entity), 0);
+
+        send_command(&mut app, HistoryCommands::Redo);
+        assert_eq!(value(&app, entity), 1);
+
+        send_command(&mut app, HistoryCommands::Redo);
+        assert_eq!(value(&app, entity), 2);
+
+        // nothing left to redo
+        send_command(&mut app, HistoryCommands::Redo);
+        assert_eq!(value(&app, entity), 2);
+    }
+
+    #[test]
+    fn record_clears_redo() {
+        let (mut app, entity) = app_setup();
+
+        set_value(&mut app, entity, 1);
+        send_command(&mut app, HistoryCommands::Rewind);
+        set_value(&mut app, entity, 3);
+
+        send_command(&mut app, HistoryCommands::Redo);
+        assert_eq!(value(&app, entity), 3);
+    }
+
+    #[test]
+    fn reset_can_be_rewound_and_redone() {
+        let (mut app, entity) = app_setup();
+
+        set_value(&mut app, entity, 1);
+        set_value(&mut app, entity, 2);
+
+        send_command(&mut app, HistoryCommands::Reset);
+        assert_eq!(value(&app, entity), 0);
+
+        send_command(&mut app, HistoryCommands::Rewind);
+        assert_eq!(value(&app, entity), 2);
+
+        send_command(&mut app, HistoryCommands::Redo);
+        assert_eq!(value(&app, entity), 0);
+    }
+
+    #[test]
+    fn despawned_entities_are_respawned() {
+        let (mut app, entity) = lifecycle_app_setup();
+
+        set_value(&mut app, entity, 1);
+        send_command(&mut app, HistoryCommands::Record);
+        with_commands(&mut app, |commands| {
+            commands.entity(entity).despawn_with_history();
+        });
+        assert_eq!(values(&mut app), Vec::<i32>::new());
+
+        send_command(&mut app, HistoryCommands::Rewind);
+        assert_eq!(values(&mut app), vec![1]);
+
+        // the respawned entity keeps its component history
+        send_command(&mut app, HistoryCommands::Rewind);
+        assert_eq!(values(&mut app), vec![0]);
+
+        send_command(&mut app, HistoryCommands::Redo);
+        assert_eq!(values(&mut app), vec![1]);
+
+        send_command(&mut app, HistoryCommands::Redo);
+        assert_eq!(values(&mut app), Vec::<i32>::new());
+
+        send_command(&mut app, HistoryCommands::Rewind);
+        assert_eq!(values(&mut app), vec![1]);
+    }
+
+    #[test]
+    fn only_registered_components_are_respawned() {
+        let (mut app, entity) = lifecycle_app_setup();
+
+        app.register_type::<Label>();
+        app.world.entity_mut(entity).insert((Label(3), Unreflected));
+
+        send_command(&mut app, HistoryCommands::Record);
+        with_commands(&mut app, |commands| {
+            commands.entity(entity).despawn_with_history();
+        });
+        send_command(&mut app, HistoryCommands::Rewind);
+
+        let (respawned, label) = app.world.query::<(Entity, &Label)>().single(&app.world);
+        assert_eq!(*label, Label(3));
+        assert_eq!(app.world.get::<Value>(respawned), Some(&Value(0)));
+        assert!(app.world.get::<History<Value>>(respawned).is_some());
+        assert!(app.world.get::<Unreflected>(respawned).is_none());
+    }
+
+    #[test]
+    fn descendants_are_respawned_with_their_entity() {
+        let (mut app, entity) = lifecycle_app_setup();
+
+        let child = app.world.spawn(Value(7)).id();
+        app.world.entity_mut(entity).add_child(child);
+
+        send_command(&mut app, HistoryCommands::Record);
+        with_commands(&mut app, |commands| {
+            commands.entity(entity).despawn_with_history();
+        });
+        assert_eq!(values(&mut app), Vec::<i32>::new());
+
+        send_command(&mut app, HistoryCommands::Rewind);
+        assert_eq!(values(&mut app), vec![0, 7]);
+
+        let (respawned, children) = app.world.query::<(Entity, &Children)>().single(&app.world);
+        assert_eq!(
+            app.world.get::<Value>(respawned),
+            Some(&Value(0)),
+            "the parent should be respawned with its children"
+        );
+        assert_eq!(
+            app.world.get::<Value>(children[0]),
+            Some(&Value(7)),
+            "the child should be respawned under its parent"
+        );
+    }
+
+    #[test]
+    fn spawned_entities_are_despawned() {
+        let (mut app, entity) = lifecycle_app_setup();
+
+        send_command(&mut app, HistoryCommands::Record);
+        with_commands(&mut app, |commands| {
+            commands
+                .spawn((Value(5), History::<Value>::default()))
+                .spawned_with_history();
+        });
+        assert_eq!(values(&mut app), vec![0, 5]);
+
+        send_command(&mut app, HistoryCommands::Rewind);
+        assert_eq!(values(&mut app), vec![0]);
+
+        send_command(&mut app, HistoryCommands::Redo);
+        assert_eq!(values(&mut app), vec![0, 5]);
+
+        send_command(&mut app, HistoryCommands::Record);
+        with_commands(&mut app, |commands| {
+            commands.entity(entity).despawn_with_history();
+        });
+        assert_eq!(values(&mut app), vec![5]);
+
+        send_command(&mut app, HistoryCommands::Reset);
+        assert_eq!(values(&mut app), vec![0]);
+
+        send_command(&mut app, HistoryCommands::Rewind);
+        assert_eq!(values(&mut app), vec![5]);
+    }
+}
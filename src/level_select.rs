@@ -1,11 +1,15 @@
 //! Plugin providing functionality for the level select card/menu.
 use crate::{
     event_scheduler::{EventScheduler, EventSchedulerPlugin},
-    graveyard::GraveyardAction,
+    graveyard::{
+        move_counter::{level_par, LevelScore},
+        GraveyardAction,
+    },
     level_transition::TransitionTo,
     nine_slice::{
         generate_nineslice_image, texture_atlas_from_nine_slice, NineSliceIndex, NineSliceSize,
     },
+    save::SaveData,
     ui::{
         action::{UiAction, UiActionPlugin},
         font_scale::{FontScale, FontSize},
@@ -15,7 +19,7 @@ use crate::{
 };
 use bevy::prelude::*;
 use bevy_easings::*;
-use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_ldtk::{ldtk::Level, prelude::*};
 use leafwing_input_manager::prelude::*;
 use std::time::Duration;
 
@@ -64,6 +68,41 @@ enum LevelSelectAction {
     GoToLevel(LevelSelection),
 }
 
+/// Identifiers of the levels that must be completed before the level at `index` is unlocked.
+///
+/// These are listed in the level's "Prerequisites" LDtk field.
+/// If none are listed, the previous level is the only prerequisite.
+fn level_prerequisites<'a>(levels: &[&'a Level], index: usize) -> Vec<&'a str> {
+    let prerequisites: Vec<&str> = levels[index]
+        .get_maybe_strings_field("Prerequisites")
+        .map(|prerequisites| prerequisites.iter().flatten().map(String::as_str).collect())
+        .unwrap_or_default();
+
+    match index.checked_sub(1) {
+        Some(previous) if prerequisites.is_empty() => vec![levels[previous].identifier.as_str()],
+        _ => prerequisites,
+    }
+}
+
+/// Text for a level's button, marking completed levels with their star count.
+///
+/// Completed levels without a par are simply marked "ok".
+fn level_button_text(level: &Level, index: usize, save_data: &SaveData) -> String {
+    let Some(progress) = save_data.completed_levels.get(&level.identifier) else {
+        return format!("#{}", index + 1);
+    };
+
+    let score = LevelScore {
+        moves: progress.best_moves,
+        par: level_par(level),
+    };
+
+    match score.stars() {
+        Some(stars) => format!("#{} {}", index + 1, "*".repeat(stars as usize)),
+        None => format!("#{} ok", index + 1),
+    }
+}
+
 fn level_select_card_style(position: UiRect) -> Style {
     let UiRect {
         left,
@@ -91,6 +130,7 @@ fn spawn_level_select_card(
     asset_holder: Res<AssetHolder>,
     mut images: ResMut<Assets<Image>>,
     ldtk_assets: Res<Assets<LdtkProject>>,
+    save_data: Res<SaveData>,
     mut event_writer: EventWriter<LevelSelectCardEvent>,
 ) {
     // TODO: refactor this to avoid repeated code with spawn_level_card
@@ -183,19 +223,34 @@ fn spawn_level_select_card(
                     ..default()
                 })
                 .with_children(|parent| {
-                    // spawn a button for every level
+                    // spawn a button for every level, only unlocked ones are selectable
                     if let Some(ldtk) = ldtk_assets.get(&asset_holder.ldtk) {
-                        for (i, _) in ldtk.iter_raw_levels().enumerate() {
-                            text_button::spawn(
+                        let levels: Vec<&Level> = ldtk.iter_raw_levels().collect();
+
+                        for (i, level) in levels.iter().enumerate() {
+                            let unlocked = save_data.all_completed(level_prerequisites(&levels, i));
+
+                            let mut button = text_button::spawn(
                                 parent,
-                                format!("#{}", i + 1),
+                                level_button_text(level, i, &save_data),
                                 &asset_holder,
                                 Val::Percent(2.),
                                 FontSize::Medium,
-                            )
-                            .insert(UiAction(
-                                LevelSelectAction::GoToLevel(LevelSelection::index(i)),
-                            ));
+                                if unlocked {
+                                    Color::WHITE
+                                } else {
+                                    Color::DARK_GRAY
+                                },
+                            );
+
+                            if unlocked {
+                                button.insert(UiAction(LevelSelectAction::GoToLevel(
+                                    LevelSelection::index(i),
+                                )));
+                            } else {
+                                // locked buttons shouldn't respond to interaction at all
+                                button.remove::<Interaction>();
+                            }
                         }
                     }
                 });
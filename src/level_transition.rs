@@ -1,6 +1,7 @@
 //! Plugin providing functionality for level transitions.
 use crate::{
     event_scheduler::{EventScheduler, EventSchedulerPlugin},
+    graveyard::move_counter::LevelScore,
     nine_slice::{
         generate_nineslice_image, texture_atlas_from_nine_slice, NineSliceIndex, NineSliceSize,
     },
@@ -98,6 +99,7 @@ fn spawn_level_card(
     assets: Res<AssetServer>,
     asset_holder: Res<AssetHolder>,
     mut images: ResMut<Assets<Image>>,
+    level_score: Option<Res<LevelScore>>,
 ) {
     let mut title = "Thank you for playing!\n\nMade by Trevor Lovell and Gabe Machado\n\nWayfarer's Toy Box font by Chequered Ink".to_string();
     let mut level_num = None;
@@ -175,6 +177,30 @@ fn spawn_level_card(
             ),
         )
         .with_children(|parent| {
+            if let Some(level_score) = level_score {
+                let score = match (level_score.par, level_score.stars()) {
+                    (Some(par), Some(stars)) => format!(
+                        "Moves: {} / Par: {par}\nStars: {stars}/3\n",
+                        level_score.moves
+                    ),
+                    _ => format!("Moves: {}\n", level_score.moves),
+                };
+
+                parent
+                    .spawn(TextBundle {
+                        text: Text::from_section(
+                            score,
+                            TextStyle {
+                                font: assets.load("fonts/WayfarersToyBoxRegular-gxxER.ttf"),
+                                color: Color::WHITE,
+                                ..default()
+                            },
+                        )
+                        .with_alignment(TextAlignment::Center),
+                        ..Default::default()
+                    })
+                    .insert(FontScale::from(FontSize::Medium));
+            }
             if let Some(level_num) = level_num {
                 parent
                     .spawn(TextBundle {
@@ -0,0 +1,75 @@
+//! TODO: provide crate documentation after writing README.
+// these two lints are triggered by normal system code a lot
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+#![warn(missing_docs)]
+
+pub mod animation;
+pub mod camera;
+pub mod event_scheduler;
+pub mod from_component;
+pub mod graveyard;
+pub mod history;
+pub mod level_select;
+pub mod level_transition;
+pub mod nine_slice;
+pub mod previous_component;
+pub mod save;
+pub mod sokoban;
+pub mod ui;
+pub mod ui_atlas_image;
+pub mod utils;
+
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+/// Length of the sides of tiles on the game-grid in bevy's coordinate space.
+pub const UNIT_LENGTH: i32 = 32;
+
+/// All possible bevy states that the game can be in.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, States)]
+pub enum GameState {
+    #[default]
+    /// Initial state of the game that perpares assets with `bevy_asset_loader`.
+    AssetLoading,
+    /// State that facilitates level transitions, see [level_transition].
+    LevelTransition,
+    /// State for the core gameplay that takes place on graveyards, see [graveyard].
+    Graveyard,
+    /// State for the level select menu, see [level_select].
+    LevelSelect,
+}
+
+/// Asset collection loaded during the `GameState::AssetLoading` state.
+///
+/// Each field provides a handle for a different core asset of the game.
+#[derive(Debug, Default, AssetCollection, Resource)]
+pub struct AssetHolder {
+    /// Handle for all the LDtk info (level design).
+    #[asset(path = "levels/willos-graveyard.ldtk")]
+    pub ldtk: Handle<LdtkProject>,
+    /// Handle for the game's spooky font.
+    #[asset(path = "fonts/WayfarersToyBoxRegular-gxxER.ttf")]
+    pub font: Handle<Font>,
+    /// Handle for the image used to underline text on text buttons.
+    #[asset(path = "textures/button-underline.png")]
+    pub button_underline: Handle<Image>,
+    /// Handle for the image used to highlight buttons on hover.
+    #[asset(path = "textures/button-radial.png")]
+    pub button_radial: Handle<Image>,
+    /// Handle for the sound that plays on level completion.
+    #[asset(path = "sfx/victory.wav")]
+    pub victory_sound: Handle<AudioSource>,
+    /// Handle for the sound that plays when Willo pushes a gravestone.
+    #[asset(path = "sfx/push.wav")]
+    pub push_sound: Handle<AudioSource>,
+    /// Handle for the sound that plays when the player hits undo/reset.
+    #[asset(path = "sfx/undo.wav")]
+    pub undo_sound: Handle<AudioSource>,
+    /// Handle for the sound that plays when Willo bumps into something they can't move.
+    #[asset(path = "sfx/thud.wav")]
+    pub thud_sound: Handle<AudioSource>,
+    /// Handle for the tarot-card-inspired 9-slice image.
+    #[asset(path = "textures/tarot.png")]
+    pub tarot_sheet: Handle<Image>,
+}
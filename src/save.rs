@@ -0,0 +1,424 @@
+//! Plugin providing persistent level progress via a save file in the user's data directory.
+//!
+//! The save file is versioned JSON.
+//! Missing save files are treated as a new game.
+//! Unreadable ones are reported as [SaveError]s and moved aside to `save.json.bak` before a new game
+//! starts, while save files written by an unsupported version of the game are left alone, and
+//! progress isn't saved at all.
+use crate::{
+    graveyard::move_counter::LevelScore, level_transition::TransitionTo,
+    utils::current_level_identifier, AssetHolder, GameState,
+};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Current version of the save file format.
+///
+/// Increment this whenever [SaveData] changes in a way that old save files can't be read as.
+pub const SAVE_VERSION: u64 = 1;
+
+/// Plugin providing persistent level progress via a save file in the user's data directory.
+///
+/// Loads the save file into the [SaveData] resource when the plugin is built.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        let save_data = match load() {
+            Ok(save_data) => save_data,
+            Err(e @ SaveError::UnsupportedVersion(_)) => {
+                warn!("{e}, starting a new game without saving progress");
+                app.insert_resource(SaveLocked);
+                SaveData::default()
+            }
+            Err(e) => {
+                warn!("unable to load save file, starting a new game: {e}");
+                match back_up() {
+                    Ok(Some(backup_path)) => {
+                        warn!("moved unreadable save file to {}", backup_path.display())
+                    }
+                    Ok(None) => (),
+                    Err(e) => warn!("unable to move unreadable save file aside: {e}"),
+                }
+                SaveData::default()
+            }
+        };
+
+        app.insert_resource(save_data)
+            .add_systems(
+                Update,
+                record_level_completion
+                    .run_if(resource_added::<LevelScore>())
+                    .run_if(not(resource_exists::<SaveLocked>())),
+            )
+            .add_systems(OnExit(GameState::AssetLoading), forget_missing_last_played)
+            .add_systems(
+                OnExit(GameState::LevelTransition),
+                record_last_played.run_if(not(resource_exists::<SaveLocked>())),
+            );
+    }
+}
+
+/// Errors encountered when loading or writing the save file.
+#[derive(Debug, Error)]
+pub enum SaveError {
+    /// The platform has no data directory to store the save file in.
+    #[error("unable to find the user's data directory")]
+    NoDataDirectory,
+    /// The save file could not be read or written.
+    #[error("save file io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The save file is not valid JSON, or does not match the [SaveData] format.
+    #[error("save file is corrupted: {0}")]
+    Corrupted(#[from] serde_json::Error),
+    /// The save file does not declare a version.
+    #[error("save file is missing its version")]
+    MissingVersion,
+    /// The save file was written by an incompatible version of the game.
+    #[error("save file version {0} is not supported, expected version {SAVE_VERSION}")]
+    UnsupportedVersion(u64),
+}
+
+/// Marker resource preventing the save file from being written.
+///
+/// Inserted when the save file was written by an unsupported version of the game, so that playing
+/// an older version doesn't overwrite it.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Resource)]
+pub struct SaveLocked;
+
+/// Progress made on a single level.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Serialize, Deserialize)]
+pub struct LevelProgress {
+    /// The fewest grave-actions the level has been completed in.
+    pub best_moves: u32,
+}
+
+/// Resource storing all persistent progress.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize, Resource)]
+pub struct SaveData {
+    /// Progress of completed levels, keyed by level identifier.
+    pub completed_levels: BTreeMap<String, LevelProgress>,
+    /// Identifier of the level that was last played.
+    pub last_played: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VersionedSaveData {
+    version: u64,
+    #[serde(flatten)]
+    data: SaveData,
+}
+
+impl SaveData {
+    /// Parses save data from the contents of a save file.
+    pub fn from_json(json: &str) -> Result<SaveData, SaveError> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+
+        match value.get("version").and_then(|version| version.as_u64()) {
+            Some(SAVE_VERSION) => {
+                let VersionedSaveData { data, .. } = serde_json::from_value(value)?;
+                Ok(data)
+            }
+            Some(version) => Err(SaveError::UnsupportedVersion(version)),
+            None => Err(SaveError::MissingVersion),
+        }
+    }
+
+    /// Serializes save data to the contents of a save file.
+    pub fn to_json(&self) -> Result<String, SaveError> {
+        Ok(serde_json::to_string_pretty(&VersionedSaveData {
+            version: SAVE_VERSION,
+            data: self.clone(),
+        })?)
+    }
+
+    /// Records the completion of a level, keeping the best move count.
+    pub fn complete_level(&mut self, identifier: impl Into<String>, moves: u32) {
+        self.completed_levels
+            .entry(identifier.into())
+            .and_modify(|progress| progress.best_moves = progress.best_moves.min(moves))
+            .or_insert(LevelProgress { best_moves: moves });
+    }
+
+    /// Forgets the level last played if it isn't one of the given level identifiers.
+    ///
+    /// Returns the identifier of the forgotten level, if any.
+    pub fn forget_missing_last_played<'a>(
+        &mut self,
+        identifiers: impl IntoIterator<Item = &'a str>,
+    ) -> Option<String> {
+        let last_played = self.last_played.as_deref()?;
+
+        if identifiers
+            .into_iter()
+            .any(|identifier| identifier == last_played)
+        {
+            None
+        } else {
+            self.last_played.take()
+        }
+    }
+
+    /// Whether every level in `identifiers` has been completed.
+    pub fn all_completed<'a>(&self, identifiers: impl IntoIterator<Item = &'a str>) -> bool {
+        identifiers
+            .into_iter()
+            .all(|identifier| self.completed_levels.contains_key(identifier))
+    }
+}
+
+/// Directory in the user's data directory where the game stores its files.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn game_data_dir() -> Option<std::path::PathBuf> {
+    dirs::data_dir().map(|data_dir| data_dir.join("willos-graveyard"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_path() -> Result<std::path::PathBuf, SaveError> {
+    Ok(game_data_dir()
+        .ok_or(SaveError::NoDataDirectory)?
+        .join("save.json"))
+}
+
+/// Loads the save file, returning default [SaveData] if it doesn't exist yet.
+pub fn load() -> Result<SaveData, SaveError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        match std::fs::read_to_string(save_path()?) {
+            Ok(json) => SaveData::from_json(&json),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(SaveData::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        Ok(SaveData::default())
+    }
+}
+
+/// Moves the save file aside to `save.json.bak`, replacing any previous backup.
+///
+/// Returns the path of the backup, or `None` if there is no save file to back up.
+pub fn back_up() -> Result<Option<std::path::PathBuf>, SaveError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        back_up_file(&save_path()?)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        Ok(None)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn back_up_file(path: &std::path::Path) -> Result<Option<std::path::PathBuf>, SaveError> {
+    let backup_path = path.with_extension("json.bak");
+
+    match std::fs::rename(path, &backup_path) {
+        Ok(()) => Ok(Some(backup_path)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Writes the save file, replacing any existing one.
+///
+/// The data is written to a temporary file first so that a failed write can't corrupt an existing
+/// save file.
+pub fn write(save_data: &SaveData) -> Result<(), SaveError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = save_path()?;
+        let temp_path = path.with_extension("json.tmp");
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&temp_path, save_data.to_json()?)?;
+        std::fs::rename(temp_path, path)?;
+
+        Ok(())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = save_data;
+        Ok(())
+    }
+}
+
+fn write_or_warn(save_data: &SaveData) {
+    if let Err(e) = write(save_data) {
+        warn!("unable to write save file: {e}");
+    }
+}
+
+fn record_level_completion(
+    mut save_data: ResMut<SaveData>,
+    level_score: Res<LevelScore>,
+    level_selection: Res<LevelSelection>,
+    ldtk_assets: Res<Assets<LdtkProject>>,
+    asset_holder: Res<AssetHolder>,
+) {
+    if let Some(identifier) =
+        current_level_identifier(&level_selection, &ldtk_assets, &asset_holder)
+    {
+        save_data.complete_level(identifier, level_score.moves);
+        write_or_warn(&save_data);
+    }
+}
+
+/// Starts from the first level instead of the level last played if it was removed from the project.
+fn forget_missing_last_played(
+    mut save_data: ResMut<SaveData>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut transition_to: ResMut<TransitionTo>,
+    ldtk_assets: Res<Assets<LdtkProject>>,
+    asset_holder: Res<AssetHolder>,
+) {
+    let Some(ldtk_project) = ldtk_assets.get(&asset_holder.ldtk) else {
+        return;
+    };
+
+    let identifiers = ldtk_project
+        .iter_raw_levels()
+        .map(|level| level.identifier.as_str());
+
+    if let Some(identifier) = save_data.forget_missing_last_played(identifiers) {
+        warn!("last played level {identifier} doesn't exist, starting from the first level");
+
+        // levels given on the command line take precedence over the level last played
+        if *level_selection == LevelSelection::Identifier(identifier) {
+            *level_selection = LevelSelection::index(0);
+            **transition_to = LevelSelection::index(0);
+        }
+    }
+}
+
+fn record_last_played(
+    mut save_data: ResMut<SaveData>,
+    level_selection: Res<LevelSelection>,
+    ldtk_assets: Res<Assets<LdtkProject>>,
+    asset_holder: Res<AssetHolder>,
+) {
+    if let Some(identifier) =
+        current_level_identifier(&level_selection, &ldtk_assets, &asset_holder)
+    {
+        save_data.last_played = Some(identifier);
+        write_or_warn(&save_data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_data_round_trips() {
+        let mut save_data = SaveData::default();
+        save_data.complete_level("Jaw", 12);
+        save_data.complete_level("Jaw", 15);
+        save_data.complete_level("Trocar", 30);
+        save_data.last_played = Some("Trocar".to_string());
+
+        assert_eq!(
+            save_data.completed_levels["Jaw"],
+            LevelProgress { best_moves: 12 }
+        );
+
+        assert_eq!(
+            SaveData::from_json(&save_data.to_json().unwrap()).unwrap(),
+            save_data
+        );
+    }
+
+    #[test]
+    fn all_completed_requires_every_level() {
+        let mut save_data = SaveData::default();
+        save_data.complete_level("Jaw", 12);
+
+        assert!(save_data.all_completed([]));
+        assert!(save_data.all_completed(["Jaw"]));
+        assert!(!save_data.all_completed(["Jaw", "Trocar"]));
+
+        save_data.complete_level("Trocar", 30);
+
+        assert!(save_data.all_completed(["Jaw", "Trocar"]));
+    }
+
+    #[test]
+    fn missing_last_played_is_forgotten() {
+        let mut save_data = SaveData {
+            last_played: Some("Jaw".to_string()),
+            ..default()
+        };
+
+        assert_eq!(
+            save_data.forget_missing_last_played(["Jaw", "Trocar"]),
+            None
+        );
+        assert_eq!(save_data.last_played.as_deref(), Some("Jaw"));
+
+        assert_eq!(
+            save_data.forget_missing_last_played(["Trocar"]),
+            Some("Jaw".to_string())
+        );
+        assert_eq!(save_data.last_played, None);
+    }
+
+    #[test]
+    fn corrupted_save_is_an_error() {
+        assert!(matches!(
+            SaveData::from_json("{\"version\": 1, \"completed_levels\": "),
+            Err(SaveError::Corrupted(_))
+        ));
+
+        assert!(matches!(
+            SaveData::from_json("{\"version\": 1, \"completed_levels\": 5}"),
+            Err(SaveError::Corrupted(_))
+        ));
+    }
+
+    #[test]
+    fn unversioned_or_old_save_is_an_error() {
+        assert!(matches!(
+            SaveData::from_json("{\"completed_levels\": {}}"),
+            Err(SaveError::MissingVersion)
+        ));
+
+        assert!(matches!(
+            SaveData::from_json("{\"version\": 0, \"completed_levels\": {}}"),
+            Err(SaveError::UnsupportedVersion(0))
+        ));
+    }
+
+    #[test]
+    fn unreadable_save_is_backed_up() {
+        let directory = std::env::temp_dir().join(format!("save-backup-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("save.json");
+
+        assert_eq!(back_up_file(&path).unwrap(), None);
+
+        std::fs::write(&path, "{\"version\": 1, \"completed_levels\": ").unwrap();
+
+        let backup_path = back_up_file(&path)
+            .unwrap()
+            .expect("save file should be backed up");
+
+        assert_eq!(backup_path, directory.join("save.json.bak"));
+        assert!(!path.exists());
+        assert_eq!(
+            std::fs::read_to_string(&backup_path).unwrap(),
+            "{\"version\": 1, \"completed_levels\": "
+        );
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,272 @@
+//! Components and events for tracking the pushes and pulls of sokoban blocks.
+use crate::sokoban::Direction;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+/// Component that marks [SokobanBlock](super::SokobanBlock)s that should fire [PushEvent]s when
+/// they push other blocks.
+///
+/// They also fire [PushBlocked] events when the [PushRule](super::PushRule) stops them from
+/// pushing.
+#[derive(Clone, Default, Debug, Component)]
+pub struct PushTracker;
+
+/// Event that fires when a [PushTracker] entity pushes other [SokobanBlock](super::SokobanBlock)s.
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct PushEvent {
+    /// The [PushTracker] entity that pushed other [SokobanBlock](super::SokobanBlock)s.
+    pub pusher: Entity,
+    /// The direction of the push.
+    pub direction: Direction,
+    /// The list of [SokobanBlock](super::SokobanBlock) entities that were pushed.
+    pub pushed: Vec<Entity>,
+    /// The grid coordinates each pushed entity moved through, in the same order as `pushed`.
+    ///
+    /// Each path ends with the entity's final position, which is further than one step away if the
+    /// entity slid across [Slippery](super::Slippery) cells.
+    pub paths: Vec<Vec<GridCoords>>,
+}
+
+/// Event that fires when the [PushRule](super::PushRule) stops a [PushTracker] entity from pushing
+/// other [SokobanBlock](super::SokobanBlock)s, because they are too heavy or too many.
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct PushBlocked {
+    /// The [PushTracker] entity that failed to push.
+    pub pusher: Entity,
+    /// The direction of the push.
+    pub direction: Direction,
+    /// The list of [SokobanBlock](super::SokobanBlock) entities that would have been pushed,
+    /// nearest first.
+    pub blocked: Vec<Entity>,
+}
+
+/// Event that fires when a [SokobanCommand](super::SokobanCommand) can't move its entity at all.
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct MoveBlocked {
+    /// The [SokobanBlock](super::SokobanBlock) entity that couldn't move.
+    pub entity: Entity,
+    /// The direction of the attempted move.
+    pub direction: Direction,
+    /// The [SokobanBlock](super::SokobanBlock) entity that blocked the move, as reported by
+    /// [move_collision_map_entry](super::move_collision_map_entry).
+    ///
+    /// This is `None` if the move was blocked by the boundary of the grid, or by terrain like
+    /// [OneWay](super::OneWay) cells.
+    pub blocker: Option<Entity>,
+}
+
+/// Component that marks [SokobanBlock](super::SokobanBlock)s that should fire [PullEvent]s when
+/// they pull other blocks.
+#[derive(Clone, Default, Debug, Component)]
+pub struct PullTracker;
+
+/// Event that fires when a [PullTracker] entity pulls another [SokobanBlock](super::SokobanBlock).
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct PullEvent {
+    /// The [PullTracker] entity that pulled another [SokobanBlock](super::SokobanBlock).
+    pub puller: Entity,
+    /// The direction of the pull.
+    pub direction: Direction,
+    /// The [SokobanBlock](super::SokobanBlock) entity that was pulled.
+    pub pulled: Entity,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sokoban::{tests::app_setup, SokobanBlock, SokobanCommands, Strength, Weight};
+    use bevy::ecs::system::SystemState;
+
+    #[test]
+    fn push_tracker_sends_events() {
+        let mut app = app_setup();
+
+        let block_a = app
+            .world
+            .spawn((GridCoords::new(1, 1), SokobanBlock::Dynamic, PushTracker))
+            .id();
+        let block_b = app
+            .world
+            .spawn((GridCoords::new(1, 2), SokobanBlock::Dynamic))
+            .id();
+        let block_c = app
+            .world
+            .spawn((GridCoords::new(2, 2), SokobanBlock::Dynamic))
+            .id();
+
+        let mut system_state: SystemState<SokobanCommands> = SystemState::new(&mut app.world);
+        let mut sokoban_commands: SokobanCommands = system_state.get_mut(&mut app.world);
+
+        sokoban_commands.move_block(block_a, super::Direction::Up);
+        sokoban_commands.move_block(block_c, super::Direction::Left);
+
+        system_state.apply(&mut app.world);
+
+        app.update();
+
+        let events = app.world.resource::<Events<PushEvent>>();
+        let mut reader = events.get_reader();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            *reader.read(events).next().unwrap(),
+            PushEvent {
+                pusher: block_a,
+                direction: super::Direction::Up,
+                pushed: vec![block_b],
+                paths: vec![vec![GridCoords::new(1, 3)]],
+            }
+        );
+    }
+
+    #[test]
+    fn blocked_moves_send_events() {
+        let mut app = app_setup();
+
+        let block = app
+            .world
+            .spawn((GridCoords::new(1, 1), SokobanBlock::Dynamic))
+            .id();
+        let wall = app
+            .world
+            .spawn((GridCoords::new(1, 2), SokobanBlock::Static))
+            .id();
+
+        let mut system_state: SystemState<SokobanCommands> = SystemState::new(&mut app.world);
+        let mut sokoban_commands: SokobanCommands = system_state.get_mut(&mut app.world);
+
+        sokoban_commands.move_block(block, super::Direction::Up);
+        sokoban_commands.pull_block(block, super::Direction::Right);
+        sokoban_commands.move_block(block, super::Direction::Right);
+
+        system_state.apply(&mut app.world);
+
+        app.update();
+
+        let events = app.world.resource::<Events<MoveBlocked>>();
+        let mut reader = events.get_reader();
+
+        assert_eq!(
+            reader.read(events).cloned().collect::<Vec<_>>(),
+            vec![
+                MoveBlocked {
+                    entity: block,
+                    direction: super::Direction::Up,
+                    blocker: Some(wall),
+                },
+                MoveBlocked {
+                    entity: block,
+                    direction: super::Direction::Right,
+                    blocker: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn push_tracker_sends_push_blocked_events() {
+        let mut app = app_setup();
+
+        let block_a = app
+            .world
+            .spawn((
+                GridCoords::new(1, 0),
+                SokobanBlock::Dynamic,
+                PushTracker,
+                Strength(2),
+            ))
+            .id();
+        let block_b = app
+            .world
+            .spawn((GridCoords::new(1, 1), SokobanBlock::Dynamic, Weight(2)))
+            .id();
+        let block_c = app
+            .world
+            .spawn((GridCoords::new(1, 2), SokobanBlock::Dynamic))
+            .id();
+
+        let mut system_state: SystemState<SokobanCommands> = SystemState::new(&mut app.world);
+        let mut sokoban_commands: SokobanCommands = system_state.get_mut(&mut app.world);
+
+        // block_b and block_c are too heavy to push together
+        sokoban_commands.move_block(block_a, super::Direction::Up);
+        // but block_b alone is exactly as heavy as block_a is strong
+        sokoban_commands.move_block(block_c, super::Direction::Right);
+        sokoban_commands.move_block(block_a, super::Direction::Up);
+
+        system_state.apply(&mut app.world);
+
+        app.update();
+
+        assert_eq!(
+            *app.world.entity(block_a).get::<GridCoords>().unwrap(),
+            GridCoords::new(1, 1)
+        );
+        assert_eq!(
+            *app.world.entity(block_b).get::<GridCoords>().unwrap(),
+            GridCoords::new(1, 2)
+        );
+        assert_eq!(
+            *app.world.entity(block_c).get::<GridCoords>().unwrap(),
+            GridCoords::new(2, 2)
+        );
+
+        let events = app.world.resource::<Events<PushBlocked>>();
+        let mut reader = events.get_reader();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            *reader.read(events).next().unwrap(),
+            PushBlocked {
+                pusher: block_a,
+                direction: super::Direction::Up,
+                blocked: vec![block_b, block_c],
+            }
+        );
+    }
+
+    #[test]
+    fn pull_tracker_sends_events() {
+        let mut app = app_setup();
+
+        let block_a = app
+            .world
+            .spawn((GridCoords::new(1, 1), SokobanBlock::Dynamic, PullTracker))
+            .id();
+        let block_b = app
+            .world
+            .spawn((GridCoords::new(1, 0), SokobanBlock::Dynamic))
+            .id();
+
+        let mut system_state: SystemState<SokobanCommands> = SystemState::new(&mut app.world);
+        let mut sokoban_commands: SokobanCommands = system_state.get_mut(&mut app.world);
+
+        sokoban_commands.pull_block(block_a, super::Direction::Up);
+
+        system_state.apply(&mut app.world);
+
+        app.update();
+
+        assert_eq!(
+            *app.world.entity(block_a).get::<GridCoords>().unwrap(),
+            GridCoords::new(1, 2)
+        );
+        assert_eq!(
+            *app.world.entity(block_b).get::<GridCoords>().unwrap(),
+            GridCoords::new(1, 1)
+        );
+
+        let events = app.world.resource::<Events<PullEvent>>();
+        let mut reader = events.get_reader();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            *reader.read(events).next().unwrap(),
+            PullEvent {
+                puller: block_a,
+                direction: super::Direction::Up,
+                pulled: block_b,
+            }
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Footprints of sokoban blocks spanning several cells.
+use crate::sokoban::{collision_map_entry, CollisionMap};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use std::collections::HashMap;
+
+/// Component defining the cells that a [SokobanBlock](super::SokobanBlock) occupies, relative to
+/// its `GridCoords`.
+///
+/// Blocks without a footprint occupy the cell at their `GridCoords` only.
+/// For multi-cell blocks, the `GridCoords` are the top-left corner of the footprint's bounding box,
+/// matching the grid position of LDtk entities with a top-left pivot.
+///
+/// Multi-cell blocks move all of their cells at once, or not at all.
+/// They aren't affected by [Teleporter](super::Teleporter)s or [Slippery](super::Slippery) cells,
+/// can't pull or be pulled, and can't be pushed into
+/// [SokobanBlock::Hole](super::SokobanBlock::Hole)s.
+#[derive(Clone, Eq, PartialEq, Debug, Hash, Component)]
+pub struct Footprint(Vec<IVec2>);
+
+impl Default for Footprint {
+    fn default() -> Footprint {
+        Footprint(vec![IVec2::ZERO])
+    }
+}
+
+impl Footprint {
+    /// Constructs a footprint occupying the given cells.
+    ///
+    /// The cells are shifted so that the top-left corner of their bounding box is at the origin.
+    /// An empty list of cells results in the single-cell [Footprint::default].
+    pub fn new(cells: impl IntoIterator<Item = IVec2>) -> Footprint {
+        let cells: Vec<IVec2> = cells.into_iter().collect();
+
+        let Some(top_left) = bounding_box_top_left(&cells) else {
+            return Footprint::default();
+        };
+
+        let mut footprint = Vec::new();
+
+        for cell in cells {
+            if !footprint.contains(&(cell - top_left)) {
+                footprint.push(cell - top_left);
+            }
+        }
+
+        Footprint(footprint)
+    }
+
+    /// Constructs a rectangular footprint with the given width and height in cells.
+    ///
+    /// The rectangle extends right and down from the block's `GridCoords`.
+    pub fn rectangle(size: IVec2) -> Footprint {
+        Footprint::new(
+            (0..size.y.max(1)).flat_map(|y| (0..size.x.max(1)).map(move |x| IVec2::new(x, -y))),
+        )
+    }
+
+    /// The cells occupied by the block, relative to its `GridCoords`.
+    pub fn cells(&self) -> &[IVec2] {
+        &self.0
+    }
+
+    /// The cells occupied by a block with these cells at the given coordinates.
+    pub fn cells_at(&self, coords: IVec2) -> impl Iterator<Item = IVec2> + '_ {
+        self.0.iter().map(move |cell| coords + *cell)
+    }
+
+    /// Offset from the center of the block's `GridCoords` cell to the center of the footprint's
+    /// bounding box, in cells.
+    pub fn center_offset(&self) -> Vec2 {
+        let max = self.0.iter().fold(IVec2::ZERO, |max, cell| max.max(*cell));
+        let min = self.0.iter().fold(IVec2::ZERO, |min, cell| min.min(*cell));
+
+        (max + min).as_vec2() / 2.
+    }
+}
+
+/// Top-left corner of the bounding box of the given cells, where up is positive y.
+pub(super) fn bounding_box_top_left(cells: &[IVec2]) -> Option<IVec2> {
+    Some(IVec2::new(
+        cells.iter().map(|cell| cell.x).min()?,
+        cells.iter().map(|cell| cell.y).max()?,
+    ))
+}
+
+/// Lookup of the [Footprint]s of [CollisionMap] entries, so that entries occupying several cells
+/// are moved as a whole.
+pub trait Footprints<T> {
+    /// The footprint of the given entry, or `None` if it occupies a single cell.
+    fn footprint(&self, entry: T) -> Option<&Footprint>;
+}
+
+/// Lookup for collision_maps whose entries all occupy a single cell.
+impl<T> Footprints<T> for () {
+    fn footprint(&self, _: T) -> Option<&Footprint> {
+        None
+    }
+}
+
+impl<T: Eq + std::hash::Hash> Footprints<T> for HashMap<T, Footprint> {
+    fn footprint(&self, entry: T) -> Option<&Footprint> {
+        self.get(&entry)
+    }
+}
+
+/// Returns `true` if the entry occupies more than one cell.
+pub(super) fn is_multi_cell<T>(footprints: &impl Footprints<T>, entry: T) -> bool {
+    footprints
+        .footprint(entry)
+        .is_some_and(|footprint| footprint.cells().len() > 1)
+}
+
+/// Coordinates of every cell of the collision_map occupied by the entry in the given cell, in
+/// row order.
+pub(super) fn entry_cells<T: Copy + PartialEq>(
+    collision_map: &CollisionMap<T>,
+    footprints: &impl Footprints<T>,
+    entry: T,
+    cell: IVec2,
+) -> Vec<IVec2> {
+    let Some(footprint) = footprints
+        .footprint(entry)
+        .filter(|footprint| footprint.cells().len() > 1)
+    else {
+        return vec![cell];
+    };
+
+    let occupied = |cell| {
+        matches!(
+            collision_map_entry(collision_map, cell),
+            Some(Some((other, _))) if other == entry
+        )
+    };
+
+    // the given cell is one of the footprint's cells, the right one has the entry in all of them
+    footprint
+        .cells()
+        .iter()
+        .find_map(|offset| {
+            let mut cells: Vec<IVec2> = footprint.cells_at(cell - *offset).collect();
+
+            cells.sort_by_key(|cell| (cell.y, cell.x));
+
+            cells.iter().all(|cell| occupied(*cell)).then_some(cells)
+        })
+        .unwrap_or_else(|| vec![cell])
+}
+
+/// The cells occupied by a block at the given coordinates.
+pub(super) fn block_cells(grid_coords: &GridCoords, footprint: Option<&Footprint>) -> Vec<IVec2> {
+    match footprint {
+        Some(footprint) => footprint.cells_at(IVec2::from(*grid_coords)).collect(),
+        None => vec![IVec2::from(*grid_coords)],
+    }
+}
+
+/// The cell a block moves by, see [Footprint].
+pub(super) fn mover_coords(grid_coords: &GridCoords, footprint: Option<&Footprint>) -> IVec2 {
+    let grid_coords = IVec2::from(*grid_coords);
+
+    // multi-cell blocks are moved by one of their cells, which may not be at their GridCoords
+    footprint.map_or(grid_coords, |footprint| grid_coords + footprint.cells()[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn footprint_is_relative_to_top_left() {
+        assert_eq!(
+            Footprint::rectangle(IVec2::new(2, 1)).cells(),
+            &[IVec2::new(0, 0), IVec2::new(1, 0)]
+        );
+
+        // an L-shape whose top-left corner isn't one of its cells
+        let footprint = Footprint::new([IVec2::new(3, 3), IVec2::new(3, 4), IVec2::new(2, 3)]);
+
+        assert_eq!(
+            footprint.cells(),
+            &[IVec2::new(1, -1), IVec2::new(1, 0), IVec2::new(0, -1)]
+        );
+        assert_eq!(footprint.center_offset(), Vec2::new(0.5, -0.5));
+    }
+}
@@ -0,0 +1,475 @@
+//! Persistent occupancy of the sokoban grid.
+use crate::sokoban::{
+    block_cells, collision_map_entry, CollisionMap, Footprint, LayerInteractions, SokobanBlock,
+    SokobanLayer, SokobanLayerIdentifier,
+};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use std::collections::HashMap;
+
+/// Resource storing which [SokobanBlock]s occupy each cell of the sokoban layer.
+///
+/// The grid is rebuilt when the sokoban layer is spawned or the [LayerInteractions] change, and
+/// kept in sync incrementally as blocks are added, moved, changed or removed after that.
+/// [SokobanCommands](super::SokobanCommands) are resolved against the grid directly, so it is up to
+/// date after [SokobanSets::LogicalMovement](super::SokobanSets::LogicalMovement).
+///
+/// Each cell holds a stack of blocks, in the order they were placed.
+/// Blocks on layers that don't interact can share a cell freely.
+/// Blocks that do interact only share a cell while other systems resolve what happens to them,
+/// like a block that fell into a [SokobanBlock::Hole].
+#[derive(Clone, Default, Eq, PartialEq, Debug, Resource)]
+pub struct SokobanGrid {
+    size: IVec2,
+    interactions: LayerInteractions,
+    /// For each layer with blocks on it, the collision_map of the blocks it interacts with.
+    pub(super) collision_maps: HashMap<SokobanLayer, CollisionMap>,
+    pub(super) blocks: HashMap<Entity, GridBlock>,
+    /// The footprints of blocks occupying several cells, relative to their top-left corner.
+    pub(super) footprints: HashMap<Entity, Footprint>,
+    /// The blocks placed on each cell, in the order they were placed.
+    stacks: HashMap<IVec2, Vec<Entity>>,
+}
+
+/// A block stored in the [SokobanGrid].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(super) struct GridBlock {
+    /// The in-bounds cells of the block, starting with its mover cell.
+    cells: Vec<IVec2>,
+    sokoban_block: SokobanBlock,
+    pub(super) layer: SokobanLayer,
+}
+
+impl SokobanGrid {
+    /// Constructs an empty grid with the given dimensions and [LayerInteractions].
+    pub fn new(size: IVec2, interactions: LayerInteractions) -> SokobanGrid {
+        SokobanGrid {
+            size: size.max(IVec2::ZERO),
+            interactions,
+            ..default()
+        }
+    }
+
+    /// Dimensions of the grid.
+    pub fn size(&self) -> IVec2 {
+        self.size
+    }
+
+    /// The blocks in the cell at the given coordinates, in the order they were placed.
+    pub fn stack(&self, coords: GridCoords) -> &[Entity] {
+        self.stacks
+            .get(&IVec2::from(coords))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// The block that blocks on the given layer collide with in the cell at the given coordinates,
+    /// if any.
+    ///
+    /// This is the most recently placed block whose layer interacts with the given one.
+    /// Returns `None` for empty and out-of-bounds cells.
+    pub fn at(&self, coords: GridCoords, layer: SokobanLayer) -> Option<(Entity, SokobanBlock)> {
+        self.top(IVec2::from(coords), layer)
+    }
+
+    /// The [CollisionMap] that blocks on the given layer collide with, for resolving moves with
+    /// functions like [move_collision_map_entry](super::move_collision_map_entry).
+    ///
+    /// Returns `None` if there are no blocks on the layer.
+    pub fn collision_map(&self, layer: SokobanLayer) -> Option<&CollisionMap> {
+        self.collision_maps.get(&layer)
+    }
+
+    /// Places a block on the given cells, removing it from any cells it occupied before.
+    ///
+    /// Out-of-bounds cells are ignored.
+    pub fn insert(
+        &mut self,
+        entity: Entity,
+        cells: impl IntoIterator<Item = IVec2>,
+        sokoban_block: SokobanBlock,
+        layer: SokobanLayer,
+    ) {
+        self.remove(entity);
+
+        if !self.collision_maps.contains_key(&layer) {
+            self.add_collision_map(layer);
+        }
+
+        let cells: Vec<IVec2> = cells
+            .into_iter()
+            .filter(|cell| self.contains(*cell))
+            .collect();
+
+        for cell in cells.iter() {
+            self.stacks.entry(*cell).or_default().push(entity);
+        }
+
+        if cells.len() > 1 {
+            self.footprints
+                .insert(entity, Footprint::new(cells.iter().copied()));
+        }
+
+        self.blocks.insert(
+            entity,
+            GridBlock {
+                cells: cells.clone(),
+                sokoban_block,
+                layer,
+            },
+        );
+
+        for cell in cells {
+            self.refresh(cell);
+        }
+    }
+
+    /// Removes a block from every cell it occupies.
+    pub fn remove(&mut self, entity: Entity) {
+        let Some(GridBlock { cells, .. }) = self.blocks.remove(&entity) else {
+            return;
+        };
+
+        self.footprints.remove(&entity);
+
+        for cell in cells {
+            self.remove_from_stack(entity, cell);
+            self.refresh(cell);
+        }
+    }
+
+    /// Moves blocks by the given offsets after a command was resolved against the collision_map of
+    /// the given layer.
+    ///
+    /// That collision_map is already up to date, so blocks that aren't in their new cells there
+    /// fell into a hole.
+    /// Those are removed from the grid until they are synced again.
+    pub(super) fn relocate(&mut self, layer: SokobanLayer, offsets: &[(Entity, IVec2)]) {
+        let mut changed_cells = Vec::new();
+
+        for (entity, offset) in offsets {
+            let Some(mut block) = self.blocks.remove(entity) else {
+                continue;
+            };
+
+            for cell in block.cells.iter() {
+                self.remove_from_stack(*entity, *cell);
+            }
+
+            changed_cells.extend(block.cells.iter().copied());
+
+            block.cells = block
+                .cells
+                .iter()
+                .map(|cell| *cell + *offset)
+                .filter(|cell| self.contains(*cell))
+                .collect();
+
+            let fell = block.cells.first().is_some_and(|cell| {
+                self.collision_maps
+                    .get(&layer)
+                    .and_then(|collision_map| collision_map_entry(collision_map, *cell))
+                    .flatten()
+                    .map(|(other, _)| other)
+                    != Some(*entity)
+            });
+
+            if fell {
+                self.footprints.remove(entity);
+                continue;
+            }
+
+            for cell in block.cells.iter() {
+                self.stacks.entry(*cell).or_default().push(*entity);
+            }
+
+            changed_cells.extend(block.cells.iter().copied());
+            self.blocks.insert(*entity, block);
+        }
+
+        for cell in changed_cells {
+            self.refresh(cell);
+        }
+    }
+
+    fn contains(&self, cell: IVec2) -> bool {
+        cell.cmpge(IVec2::ZERO).all() && cell.cmplt(self.size).all()
+    }
+
+    fn remove_from_stack(&mut self, entity: Entity, cell: IVec2) {
+        if let Some(stack) = self.stacks.get_mut(&cell) {
+            stack.retain(|other| *other != entity);
+
+            if stack.is_empty() {
+                self.stacks.remove(&cell);
+            }
+        }
+    }
+
+    /// The most recently placed block in the given cell whose layer interacts with the given one.
+    fn top(&self, cell: IVec2, layer: SokobanLayer) -> Option<(Entity, SokobanBlock)> {
+        self.stacks.get(&cell)?.iter().rev().find_map(|entity| {
+            let block = &self.blocks[entity];
+
+            self.interactions
+                .interact(block.layer, layer)
+                .then_some((*entity, block.sokoban_block))
+        })
+    }
+
+    /// Adds a collision_map for the given layer, filled with the blocks already in the grid.
+    fn add_collision_map(&mut self, layer: SokobanLayer) {
+        let mut collision_map = vec![vec![None; self.size.x as usize]; self.size.y as usize];
+
+        for cell in self.stacks.keys() {
+            collision_map[cell.y as usize][cell.x as usize] = self.top(*cell, layer);
+        }
+
+        self.collision_maps.insert(layer, collision_map);
+    }
+
+    /// Updates the entries of the given cell in every collision_map.
+    fn refresh(&mut self, cell: IVec2) {
+        let layers: Vec<SokobanLayer> = self.collision_maps.keys().copied().collect();
+
+        for layer in layers {
+            let entry = self.top(cell, layer);
+
+            if let Some(collision_map) = self.collision_maps.get_mut(&layer) {
+                collision_map[cell.y as usize][cell.x as usize] = entry;
+            }
+        }
+    }
+}
+
+pub(super) fn sync_sokoban_grid(
+    mut grid: ResMut<SokobanGrid>,
+    block_query: Query<(
+        Entity,
+        &GridCoords,
+        &SokobanBlock,
+        Option<&Footprint>,
+        Option<&SokobanLayer>,
+    )>,
+    changed_block_query: Query<
+        (
+            Entity,
+            &GridCoords,
+            &SokobanBlock,
+            Option<&Footprint>,
+            Option<&SokobanLayer>,
+        ),
+        Or<(
+            Added<SokobanBlock>,
+            Changed<SokobanBlock>,
+            Changed<GridCoords>,
+            Changed<Footprint>,
+            Changed<SokobanLayer>,
+        )>,
+    >,
+    mut removed_blocks: RemovedComponents<SokobanBlock>,
+    layers: Query<Ref<LayerMetadata>>,
+    layer_id: Res<SokobanLayerIdentifier>,
+    layer_interactions: Res<LayerInteractions>,
+) {
+    let Some(layer) = layers.iter().find(|l| l.identifier == **layer_id) else {
+        if *grid != SokobanGrid::default() {
+            *grid = SokobanGrid::default();
+        }
+
+        return;
+    };
+
+    let size = IVec2::new(layer.c_wid, layer.c_hei);
+
+    if layer.is_changed() || layer_interactions.is_changed() || grid.size() != size {
+        // a new level has been spawned, or blocks collide differently
+        removed_blocks.clear();
+
+        *grid = SokobanGrid::new(size, layer_interactions.clone());
+
+        for (entity, grid_coords, sokoban_block, footprint, layer) in block_query.iter() {
+            grid.insert(
+                entity,
+                block_cells(grid_coords, footprint),
+                *sokoban_block,
+                layer.copied().unwrap_or_default(),
+            );
+        }
+
+        return;
+    }
+
+    for entity in removed_blocks.read() {
+        grid.remove(entity);
+    }
+
+    for (entity, grid_coords, sokoban_block, footprint, layer) in changed_block_query.iter() {
+        grid.insert(
+            entity,
+            block_cells(grid_coords, footprint),
+            *sokoban_block,
+            layer.copied().unwrap_or_default(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sokoban::{tests::app_setup, Direction, Footprints, SokobanCommands};
+    use bevy::ecs::system::SystemState;
+
+    #[test]
+    fn sokoban_grid_stacks_blocks() {
+        let block = Entity::from_raw(0);
+        let hole = Entity::from_raw(1);
+        let layer = SokobanLayer::default();
+
+        let mut grid = SokobanGrid::new(IVec2::new(3, 2), LayerInteractions::default());
+        assert_eq!(grid.size(), IVec2::new(3, 2));
+
+        grid.insert(hole, [IVec2::new(1, 0)], SokobanBlock::Hole, layer);
+        grid.insert(
+            block,
+            [IVec2::new(0, 0), IVec2::new(1, 0), IVec2::new(3, 0)],
+            SokobanBlock::Dynamic,
+            layer,
+        );
+
+        assert_eq!(
+            grid.at(GridCoords::new(0, 0), layer),
+            Some((block, SokobanBlock::Dynamic))
+        );
+        assert_eq!(
+            grid.at(GridCoords::new(1, 0), layer),
+            Some((block, SokobanBlock::Dynamic))
+        );
+        assert_eq!(grid.stack(GridCoords::new(1, 0)), &[hole, block]);
+        assert_eq!(grid.at(GridCoords::new(3, 0), layer), None);
+
+        // footprints are looked up from the in-bounds cells of multi-cell blocks
+        assert_eq!(
+            grid.footprints.footprint(block),
+            Some(&Footprint::rectangle(IVec2::new(2, 1)))
+        );
+        assert_eq!(grid.footprints.footprint(hole), None);
+
+        // the hole is uncovered when the block moves away
+        grid.insert(block, [IVec2::new(0, 1)], SokobanBlock::Dynamic, layer);
+
+        assert_eq!(grid.footprints.footprint(block), None);
+
+        assert_eq!(grid.at(GridCoords::new(0, 0), layer), None);
+        assert_eq!(
+            grid.at(GridCoords::new(1, 0), layer),
+            Some((hole, SokobanBlock::Hole))
+        );
+
+        grid.remove(hole);
+
+        assert_eq!(grid.at(GridCoords::new(1, 0), layer), None);
+        assert_eq!(
+            grid.collision_map(layer).unwrap()[1][0],
+            Some((block, SokobanBlock::Dynamic))
+        );
+    }
+
+    #[test]
+    fn sokoban_grid_separates_layers() {
+        let gravestone = Entity::from_raw(0);
+        let fence = Entity::from_raw(1);
+        let spirit = Entity::from_raw(2);
+
+        let ground = SokobanLayer(0);
+        let fences = SokobanLayer(1);
+        let spirits = SokobanLayer(2);
+
+        let mut interactions = LayerInteractions::default();
+        interactions.insert(ground, fences);
+        interactions.insert(fences, spirits);
+
+        let mut grid = SokobanGrid::new(IVec2::new(3, 1), interactions);
+
+        grid.insert(
+            gravestone,
+            [IVec2::new(0, 0)],
+            SokobanBlock::Dynamic,
+            ground,
+        );
+        grid.insert(fence, [IVec2::new(1, 0)], SokobanBlock::Static, fences);
+        grid.insert(spirit, [IVec2::new(0, 0)], SokobanBlock::Dynamic, spirits);
+
+        // the spirit floats over the gravestone, but both collide with the fence
+        assert_eq!(grid.stack(GridCoords::new(0, 0)), &[gravestone, spirit]);
+        assert_eq!(
+            grid.at(GridCoords::new(0, 0), ground),
+            Some((gravestone, SokobanBlock::Dynamic))
+        );
+        assert_eq!(
+            grid.at(GridCoords::new(0, 0), spirits),
+            Some((spirit, SokobanBlock::Dynamic))
+        );
+        assert_eq!(
+            grid.at(GridCoords::new(1, 0), ground),
+            Some((fence, SokobanBlock::Static))
+        );
+        assert_eq!(
+            grid.at(GridCoords::new(1, 0), spirits),
+            Some((fence, SokobanBlock::Static))
+        );
+    }
+
+    #[test]
+    fn sokoban_grid_follows_blocks() {
+        let mut app = app_setup();
+
+        let block = app
+            .world
+            .spawn((GridCoords::new(1, 1), SokobanBlock::Dynamic))
+            .id();
+        let wall = app
+            .world
+            .spawn((GridCoords::new(2, 1), SokobanBlock::Static))
+            .id();
+
+        app.update();
+
+        let at = |app: &App, x, y| {
+            app.world
+                .resource::<SokobanGrid>()
+                .at(GridCoords::new(x, y), SokobanLayer::default())
+        };
+
+        assert_eq!(at(&app, 1, 1), Some((block, SokobanBlock::Dynamic)));
+        assert_eq!(at(&app, 2, 1), Some((wall, SokobanBlock::Static)));
+
+        // moved by sokoban commands
+        let mut system_state: SystemState<SokobanCommands> = SystemState::new(&mut app.world);
+        let mut sokoban_commands: SokobanCommands = system_state.get_mut(&mut app.world);
+
+        sokoban_commands.move_block(block, Direction::Up);
+
+        system_state.apply(&mut app.world);
+
+        app.update();
+
+        assert_eq!(at(&app, 1, 1), None);
+        assert_eq!(at(&app, 1, 2), Some((block, SokobanBlock::Dynamic)));
+
+        // moved by other systems, like history
+        *app.world.get_mut::<GridCoords>(block).unwrap() = GridCoords::new(0, 0);
+
+        app.update();
+
+        assert_eq!(at(&app, 1, 2), None);
+        assert_eq!(at(&app, 0, 0), Some((block, SokobanBlock::Dynamic)));
+
+        app.world.despawn(wall);
+        app.world.entity_mut(block).insert(SokobanBlock::Static);
+
+        app.update();
+
+        assert_eq!(at(&app, 2, 1), None);
+        assert_eq!(at(&app, 0, 0), Some((block, SokobanBlock::Static)));
+    }
+}
@@ -0,0 +1,104 @@
+//! Collision layers of sokoban blocks.
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+/// Component defining the collision layer of a [SokobanBlock](super::SokobanBlock).
+///
+/// Blocks only block, push or pull blocks on layers that interact with theirs, see
+/// [LayerInteractions].
+/// Blocks without a layer are on the default layer, `SokobanLayer(0)`.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Component)]
+pub struct SokobanLayer(pub u32);
+
+/// Resource defining which [SokobanLayer]s interact with each other.
+///
+/// Every layer interacts with itself, and interactions go both ways.
+/// For example, a spirit layer that interacts with a fence layer, but not with the default layer,
+/// lets spirits float over gravestones while still being blocked by fences.
+#[derive(Clone, Default, Eq, PartialEq, Debug, Resource)]
+pub struct LayerInteractions(HashSet<(SokobanLayer, SokobanLayer)>);
+
+impl LayerInteractions {
+    fn key(a: SokobanLayer, b: SokobanLayer) -> (SokobanLayer, SokobanLayer) {
+        (a.min(b), a.max(b))
+    }
+
+    /// Makes the two layers interact.
+    pub fn insert(&mut self, a: SokobanLayer, b: SokobanLayer) {
+        if a != b {
+            self.0.insert(LayerInteractions::key(a, b));
+        }
+    }
+
+    /// Stops the two layers from interacting, unless they are the same layer.
+    pub fn remove(&mut self, a: SokobanLayer, b: SokobanLayer) {
+        self.0.remove(&LayerInteractions::key(a, b));
+    }
+
+    /// Returns `true` if blocks on the two layers interact.
+    pub fn interact(&self, a: SokobanLayer, b: SokobanLayer) -> bool {
+        a == b || self.0.contains(&LayerInteractions::key(a, b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sokoban::{tests::app_setup, Direction, SokobanBlock, SokobanCommands, SokobanGrid};
+    use bevy::ecs::system::SystemState;
+    use bevy_ecs_ldtk::prelude::*;
+
+    #[test]
+    fn blocks_only_collide_with_interacting_layers() {
+        let mut app = app_setup();
+
+        let mut interactions = LayerInteractions::default();
+        interactions.insert(SokobanLayer(0), SokobanLayer(1));
+        interactions.insert(SokobanLayer(1), SokobanLayer(2));
+        app.insert_resource(interactions);
+
+        let gravestone = app
+            .world
+            .spawn((GridCoords::new(1, 1), SokobanBlock::Dynamic))
+            .id();
+        let spirit = app
+            .world
+            .spawn((
+                GridCoords::new(1, 0),
+                SokobanBlock::Dynamic,
+                SokobanLayer(2),
+            ))
+            .id();
+        app.world
+            .spawn((GridCoords::new(1, 3), SokobanBlock::Static, SokobanLayer(1)));
+
+        let mut system_state: SystemState<SokobanCommands> = SystemState::new(&mut app.world);
+        let mut sokoban_commands: SokobanCommands = system_state.get_mut(&mut app.world);
+
+        // the spirit floats over the gravestone, but is stopped by the fence
+        sokoban_commands.move_block(spirit, Direction::Up);
+        sokoban_commands.move_block(spirit, Direction::Up);
+        sokoban_commands.move_block(spirit, Direction::Up);
+        sokoban_commands.move_block(gravestone, Direction::Up);
+        sokoban_commands.move_block(gravestone, Direction::Up);
+
+        system_state.apply(&mut app.world);
+
+        app.update();
+
+        assert_eq!(
+            *app.world.entity(spirit).get::<GridCoords>().unwrap(),
+            GridCoords::new(1, 2)
+        );
+        assert_eq!(
+            *app.world.entity(gravestone).get::<GridCoords>().unwrap(),
+            GridCoords::new(1, 2)
+        );
+        assert_eq!(
+            app.world
+                .resource::<SokobanGrid>()
+                .stack(GridCoords::new(1, 2)),
+            &[spirit, gravestone]
+        );
+    }
+}
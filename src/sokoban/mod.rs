@@ -0,0 +1,746 @@
+//! Plugin providing functionality for sokoban-style movement and collision to LDtk levels.
+//!
+//! You should use `bevy_ecs_ldtk` to load the levels.
+//! Spawn entities with `GridCoords` (from `bevy_ecs_ldtk`) and [SokobanBlock]s to give them
+//! sokoban-style collision.
+//! Then, move entities around with the [SokobanCommands] system parameter.
+//! Blocks can either push the blocks in front of them, or pull the block directly behind them.
+//! Moves that can't be performed fire [MoveBlocked] events.
+//!
+//! Entities with `GridCoords` and the [Slippery] component make their cell slippery, so that
+//! [SokobanBlock::Dynamic] blocks ending a move there keep sliding until they are blocked.
+//!
+//! Entities with `GridCoords` and the [Teleporter] component relocate blocks moving into their cell
+//! to their partner's cell.
+//!
+//! Entities with `GridCoords` and the [OneWay] component only let blocks into their cell from one
+//! direction.
+//!
+//! [SokobanBlock::Hole]s block movers, but swallow blocks pushed into them.
+//! What happens to the hole and the swallowed block afterwards is up to the user.
+//!
+//! Blocks occupy the single cell at their `GridCoords`, unless they have a [Footprint] spanning
+//! several cells.
+//!
+//! How much a single push can move is limited by the [PushRule] resource, along with the
+//! [Strength] of the pusher and the [Weight] of the pushed blocks.
+//!
+//! Blocks on different [SokobanLayer]s share cells without colliding, unless the
+//! [LayerInteractions] resource says that their layers interact.
+//!
+//! The occupancy of the grid is available to other systems via the [SokobanGrid] resource.
+//! Moves can be previewed without performing them via the [SokobanPreview] system parameter.
+
+mod events;
+mod footprint;
+mod grid;
+mod layer;
+mod preview;
+mod push;
+mod terrain;
+
+pub use events::*;
+pub use footprint::*;
+pub use grid::*;
+pub use layer::*;
+pub use preview::*;
+pub use push::*;
+pub use terrain::*;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_easings::*;
+use bevy_ecs_ldtk::{prelude::*, utils::grid_coords_to_translation};
+
+/// Sets used by sokoban systems
+#[derive(Clone, Debug, PartialEq, Eq, Hash, SystemSet)]
+pub enum SokobanSets {
+    /// Set for the system that updates the visual position of sokoban entities via bevy_easings.
+    EaseMovement,
+    /// Set for the system that updates the logical position of sokoban entities.
+    LogicalMovement,
+}
+
+/// Plugin providing functionality for sokoban-style movement and collision to LDtk levels.
+pub struct SokobanPlugin<S>
+where
+    S: States,
+{
+    state: S,
+    layer_identifier: SokobanLayerIdentifier,
+    diagonal_squeeze: DiagonalSqueeze,
+    push_rule: PushRule,
+    layer_interactions: LayerInteractions,
+}
+
+impl<S> SokobanPlugin<S>
+where
+    S: States,
+{
+    /// Constructor for the plugin.
+    ///
+    /// Allows the user to specify a particular iyes_loopless state to run the plugin in.
+    ///
+    /// The `layer_identifier` should refer to a non-entity layer in LDtk that can be treated as
+    /// the Sokoban grid.
+    /// This layer should have the tile-size and dimensions for your desired sokoban functionality.
+    /// It is shared by every [SokobanLayer], which only separate blocks' collisions.
+    pub fn new(state: S, layer_identifier: impl Into<String>) -> Self {
+        let layer_identifier = SokobanLayerIdentifier(layer_identifier.into());
+        SokobanPlugin {
+            state,
+            layer_identifier,
+            diagonal_squeeze: DiagonalSqueeze::default(),
+            push_rule: PushRule::default(),
+            layer_interactions: LayerInteractions::default(),
+        }
+    }
+
+    /// Configures whether diagonal pushes can squeeze between two static blocks.
+    ///
+    /// Defaults to [DiagonalSqueeze::Blocked].
+    pub fn with_diagonal_squeeze(mut self, diagonal_squeeze: DiagonalSqueeze) -> Self {
+        self.diagonal_squeeze = diagonal_squeeze;
+        self
+    }
+
+    /// Configures the initial [PushRule], which can be changed later via the resource.
+    ///
+    /// Defaults to [PushRule::Strength].
+    pub fn with_push_rule(mut self, push_rule: PushRule) -> Self {
+        self.push_rule = push_rule;
+        self
+    }
+
+    /// Configures the two [SokobanLayer]s to interact, which can be changed later via the
+    /// [LayerInteractions] resource.
+    ///
+    /// By default, blocks only interact with blocks on their own layer.
+    pub fn with_layer_interaction(mut self, a: SokobanLayer, b: SokobanLayer) -> Self {
+        self.layer_interactions.insert(a, b);
+        self
+    }
+}
+
+impl<S> Plugin for SokobanPlugin<S>
+where
+    S: States,
+{
+    fn build(&self, app: &mut App) {
+        app.add_event::<SokobanCommand>()
+            .add_event::<PushEvent>()
+            .add_event::<PullEvent>()
+            .add_event::<PushBlocked>()
+            .add_event::<MoveBlocked>()
+            .insert_resource(self.layer_identifier.clone())
+            .insert_resource(self.diagonal_squeeze)
+            .insert_resource(self.push_rule)
+            .insert_resource(self.layer_interactions.clone())
+            .init_resource::<SokobanGrid>()
+            .init_resource::<Terrain>()
+            .add_systems(
+                Update,
+                (
+                    (sync_sokoban_grid, sync_terrain)
+                        .chain()
+                        .run_if(in_state(self.state.clone()))
+                        .before(SokobanSets::LogicalMovement),
+                    flush_sokoban_commands
+                        .run_if(in_state(self.state.clone()))
+                        .run_if(on_event::<SokobanCommand>())
+                        .in_set(SokobanSets::LogicalMovement),
+                ),
+            )
+            // Systems with potential easing end/beginning collisions cannot be in CoreSet::Update
+            // see https://github.com/vleue/bevy_easings/issues/23
+            .add_systems(
+                PostUpdate,
+                ease_movement
+                    .run_if(in_state(self.state.clone()))
+                    .in_set(SokobanSets::EaseMovement),
+            );
+    }
+}
+
+/// Resource referring to the LDtk layer that should be treated as a sokoban grid.
+#[derive(Debug, Clone, Deref, DerefMut, Resource)]
+struct SokobanLayerIdentifier(String);
+
+/// Enumerates the eight directions that sokoban blocks can be pushed in.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Direction {
+    /// North direction.
+    Up,
+    /// West direction.
+    Left,
+    /// South direction.
+    Down,
+    /// East direction.
+    Right,
+    /// Northwest direction.
+    UpLeft,
+    /// Northeast direction.
+    UpRight,
+    /// Southwest direction.
+    DownLeft,
+    /// Southeast direction.
+    DownRight,
+}
+
+impl Direction {
+    /// The two orthogonal directions making up this direction, if it is diagonal.
+    ///
+    /// The vertical direction comes first.
+    pub fn orthogonal_components(self) -> Option<(Direction, Direction)> {
+        match self {
+            Direction::UpLeft => Some((Direction::Up, Direction::Left)),
+            Direction::UpRight => Some((Direction::Up, Direction::Right)),
+            Direction::DownLeft => Some((Direction::Down, Direction::Left)),
+            Direction::DownRight => Some((Direction::Down, Direction::Right)),
+            _ => None,
+        }
+    }
+}
+
+impl From<Direction> for IVec2 {
+    fn from(direction: Direction) -> IVec2 {
+        match direction {
+            Direction::Up => IVec2::Y,
+            Direction::Left => IVec2::new(-1, 0),
+            Direction::Down => IVec2::new(0, -1),
+            Direction::Right => IVec2::X,
+            Direction::UpLeft => IVec2::new(-1, 1),
+            Direction::UpRight => IVec2::new(1, 1),
+            Direction::DownLeft => IVec2::new(-1, -1),
+            Direction::DownRight => IVec2::new(1, -1),
+        }
+    }
+}
+
+/// Enumerates commands that can be performed via [SokobanCommands].
+#[derive(Debug, Clone, Event)]
+pub enum SokobanCommand {
+    /// Move a [SokobanBlock] entity in the given direction.
+    Move {
+        /// The [SokobanBlock] entity to move.
+        entity: Entity,
+        /// The direction to move the block in.
+        direction: Direction,
+    },
+    /// Move a [SokobanBlock] entity in the given direction, dragging the block behind it along.
+    Pull {
+        /// The [SokobanBlock] entity to move.
+        entity: Entity,
+        /// The direction to move the block in.
+        direction: Direction,
+    },
+}
+
+/// System parameter providing an interface for commanding the SokobanPlugin.
+#[derive(SystemParam)]
+pub struct SokobanCommands<'w> {
+    writer: EventWriter<'w, SokobanCommand>,
+}
+
+impl SokobanCommands<'_> {
+    /// Move a [SokobanBlock] entity in the given direction.
+    ///
+    /// Will perform the necessary collision checks and block pushes.
+    pub fn move_block(&mut self, entity: Entity, direction: Direction) {
+        self.writer.send(SokobanCommand::Move { entity, direction });
+    }
+
+    /// Move a [SokobanBlock] entity in the given direction, pulling the block behind it.
+    ///
+    /// The entity can only move into an empty space, it does not push other blocks.
+    /// If a [SokobanBlock::Dynamic] block sits directly behind it, that block is dragged into the
+    /// space the entity leaves.
+    pub fn pull_block(&mut self, entity: Entity, direction: Direction) {
+        self.writer.send(SokobanCommand::Pull { entity, direction });
+    }
+}
+
+/// Component defining the behavior of sokoban entities on collision.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug, Hash, Component)]
+pub enum SokobanBlock {
+    #[default]
+    /// The entity cannot move, push, or be pushed - but can block movement.
+    Static,
+    /// The entity can move, push, or be pushed.
+    Dynamic,
+    /// The entity cannot move or push, and blocks movement - but dynamic blocks pushed into it fall
+    /// in.
+    ///
+    /// The fallen block's `GridCoords` are updated to the hole's, and both of them are removed
+    /// from the collision map for the rest of the flush.
+    Hole,
+}
+
+impl SokobanBlock {
+    /// Constructor returning [SokobanBlock::Static].
+    ///
+    /// Compatible with the `with` attribute for `#[derive(LdtkEntity)]`:
+    /// ```
+    /// use bevy::prelude::*;
+    /// use bevy_ecs_ldtk::prelude::*;
+    /// use willos_graveyard::sokoban::SokobanBlock;
+    ///
+    /// #[derive(Bundle, LdtkEntity)]
+    /// struct MyLdtkEntity {
+    ///     #[grid_coords]
+    ///     grid_coords: GridCoords,
+    ///     #[with(SokobanBlock::new_static)]
+    ///     sokoban_block: SokobanBlock,
+    /// }
+    /// ```
+    pub fn new_static(_: &EntityInstance) -> SokobanBlock {
+        SokobanBlock::Static
+    }
+
+    /// Constructor returning [SokobanBlock::Dynamic].
+    ///
+    /// Compatible with the `with` attribute for `#[derive(LdtkEntity)]`:
+    /// ```
+    /// use bevy::prelude::*;
+    /// use bevy_ecs_ldtk::prelude::*;
+    /// use willos_graveyard::sokoban::SokobanBlock;
+    ///
+    /// #[derive(Bundle, LdtkEntity)]
+    /// struct MyLdtkEntity {
+    ///     #[grid_coords]
+    ///     grid_coords: GridCoords,
+    ///     #[with(SokobanBlock::new_dynamic)]
+    ///     sokoban_block: SokobanBlock,
+    /// }
+    /// ```
+    pub fn new_dynamic(_: &EntityInstance) -> SokobanBlock {
+        SokobanBlock::Dynamic
+    }
+}
+
+/// Component marking [SokobanBlock]s that went through a [Teleporter] during their last move.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug, Hash, Component)]
+struct Teleported;
+
+fn ease_movement(
+    mut commands: Commands,
+    mut grid_coords_query: Query<
+        (
+            Entity,
+            &GridCoords,
+            &Transform,
+            Option<&Footprint>,
+            Option<&Teleported>,
+        ),
+        (Changed<GridCoords>, With<SokobanBlock>),
+    >,
+    layers: Query<&LayerMetadata>,
+    layer_id: Res<SokobanLayerIdentifier>,
+) {
+    for (entity, &grid_coords, transform, footprint, teleported) in grid_coords_query.iter_mut() {
+        if let Some(LayerMetadata { grid_size, .. }) =
+            layers.iter().find(|l| l.identifier == **layer_id)
+        {
+            // multi-cell blocks are centered on their whole footprint
+            let xy = grid_coords_to_translation(grid_coords, IVec2::splat(*grid_size))
+                + footprint.map_or(Vec2::ZERO, Footprint::center_offset) * *grid_size as f32;
+            let destination = Transform::from_xyz(xy.x, xy.y, transform.translation.z);
+
+            if teleported.is_some() {
+                // teleports shrink out of sight and pop back up at the destination rather than
+                // sliding across the map
+                let hidden = |transform: Transform| transform.with_scale(Vec3::new(0., 0., 1.));
+
+                commands.entity(entity).remove::<Teleported>().insert(
+                    transform
+                        .ease_to(
+                            hidden(*transform),
+                            EaseFunction::QuadraticIn,
+                            EasingType::Once {
+                                duration: std::time::Duration::from_millis(80),
+                            },
+                        )
+                        .ease_to(
+                            hidden(destination),
+                            EaseFunction::QuadraticInOut,
+                            EasingType::Once {
+                                duration: std::time::Duration::from_millis(1),
+                            },
+                        )
+                        .ease_to(
+                            destination,
+                            EaseFunction::BackOut,
+                            EasingType::Once {
+                                duration: std::time::Duration::from_millis(140),
+                            },
+                        ),
+                );
+
+                continue;
+            }
+
+            // slides take longer the further they go
+            let cells_moved = (xy.distance(transform.translation.truncate()) / *grid_size as f32)
+                .ceil()
+                .max(1.);
+
+            commands.entity(entity).insert(transform.ease_to(
+                destination,
+                EaseFunction::CubicOut,
+                EasingType::Once {
+                    duration: std::time::Duration::from_millis(110).mul_f32(cells_moved),
+                },
+            ));
+        }
+    }
+}
+
+fn flush_sokoban_commands(
+    mut commands: Commands,
+    mut grid_coords_query: Query<(
+        Entity,
+        &mut GridCoords,
+        &SokobanBlock,
+        Option<&Footprint>,
+        Option<&PushTracker>,
+        Option<&PullTracker>,
+    )>,
+    mut sokoban_commands: EventReader<SokobanCommand>,
+    mut push_events: EventWriter<PushEvent>,
+    mut pull_events: EventWriter<PullEvent>,
+    terrain: Res<Terrain>,
+    push_limits: PushLimits,
+    mut push_blocked_events: EventWriter<PushBlocked>,
+    mut move_blocked_events: EventWriter<MoveBlocked>,
+    mut grid: ResMut<SokobanGrid>,
+    layers: Query<&LayerMetadata>,
+    layer_id: Res<SokobanLayerIdentifier>,
+    diagonal_squeeze: Res<DiagonalSqueeze>,
+) {
+    if layers.iter().any(|l| l.identifier == **layer_id) {
+        for sokoban_command in sokoban_commands.read() {
+            let (SokobanCommand::Move { entity, direction }
+            | SokobanCommand::Pull { entity, direction }) = sokoban_command;
+
+            let Ok((_, grid_coords, _, footprint, ..)) = grid_coords_query.get(*entity) else {
+                warn!("attempted to move sokoban entity {entity:?}, but it does not exist or is malformed");
+                continue;
+            };
+
+            let mover_coords = mover_coords(grid_coords, footprint);
+            let grid_coords = IVec2::from(*grid_coords);
+
+            let Some(layer) = grid.blocks.get(entity).map(|block| block.layer) else {
+                warn!("attempted to move sokoban entity {entity:?}, but it is not in the sokoban grid");
+                continue;
+            };
+
+            // the grid is kept in sync with the GridCoords by sync_sokoban_grid, and the mover's
+            // collision_map is updated in place as the command is resolved
+            let SokobanGrid {
+                collision_maps,
+                footprints,
+                ..
+            } = &mut *grid;
+
+            let collision_map = collision_maps
+                .get_mut(&layer)
+                .expect("layers with blocks should have a collision_map");
+
+            // how far each block moved, so the rest of the grid can catch up
+            let mut offsets = Vec::new();
+
+            match sokoban_command {
+                SokobanCommand::Pull { .. } => {
+                    let pull = pull_collision_map_entry(
+                        collision_map,
+                        &terrain,
+                        footprints,
+                        mover_coords,
+                        *direction,
+                        *diagonal_squeeze,
+                    );
+
+                    if let Some((puller, pulled)) = pull {
+                        // update GridCoords components of the puller and pulled entities, sliding
+                        // them from the front
+                        let moved_entities = [(
+                            puller,
+                            grid_coords,
+                            terrain.destination(grid_coords, *direction),
+                        )]
+                        .into_iter()
+                        .chain(pulled.map(|pulled| {
+                            (pulled, grid_coords - IVec2::from(*direction), grid_coords)
+                        }));
+
+                        for (moved_entity, start, coords) in moved_entities {
+                            let mut path = vec![coords];
+                            path.extend(slide_collision_map_entry(
+                                collision_map,
+                                &terrain,
+                                coords,
+                                *direction,
+                                *diagonal_squeeze,
+                            ));
+
+                            if is_teleport(start, &path, *direction) {
+                                commands.entity(moved_entity).insert(Teleported);
+                            }
+
+                            let end = *path.last().expect("path should not be empty");
+
+                            *grid_coords_query
+                                .get_component_mut::<GridCoords>(moved_entity)
+                                .expect("pulled entity should be valid sokoban entity") =
+                                GridCoords::from(end);
+
+                            offsets.push((moved_entity, end - start));
+                        }
+
+                        // send pull event
+                        if let Some(pulled) = pulled {
+                            if let (.., Some(_)) = grid_coords_query
+                                .get(puller)
+                                .expect("puller should be valid sokoban entity")
+                            {
+                                pull_events.send(PullEvent {
+                                    puller,
+                                    direction: *direction,
+                                    pulled,
+                                });
+                            }
+                        }
+                    } else {
+                        // pullers never push, so they can only be blocked by what's in front of them
+                        move_blocked_events.send(MoveBlocked {
+                            entity: *entity,
+                            direction: *direction,
+                            blocker: collision_map_entry(
+                                collision_map,
+                                terrain.destination(mover_coords, *direction),
+                            )
+                            .flatten()
+                            .map(|(blocker, _)| blocker),
+                        });
+                    }
+                }
+                SokobanCommand::Move { .. } => {
+                    let move_result = resolve_move(
+                        collision_map,
+                        &terrain,
+                        footprints,
+                        *entity,
+                        mover_coords,
+                        *direction,
+                        *diagonal_squeeze,
+                        &push_limits,
+                    );
+
+                    match move_result {
+                        Ok(moved_entities) => {
+                            let paths: Vec<Vec<GridCoords>> = moved_entities
+                                .iter()
+                                .map(|(_, path)| {
+                                    path.iter().copied().map(GridCoords::from).collect()
+                                })
+                                .collect();
+
+                            // update GridCoords components of pushed entities
+                            for (pushed_entity, path) in moved_entities.iter() {
+                                let mut pushed_grid_coords = grid_coords_query
+                                    .get_component_mut::<GridCoords>(*pushed_entity)
+                                    .expect("pushed entity should be valid sokoban entity");
+
+                                if is_teleport(IVec2::from(*pushed_grid_coords), path, *direction) {
+                                    commands.entity(*pushed_entity).insert(Teleported);
+                                }
+
+                                let end = *path
+                                    .last()
+                                    .expect("moved entity should have moved at least once");
+
+                                offsets
+                                    .push((*pushed_entity, end - IVec2::from(*pushed_grid_coords)));
+
+                                *pushed_grid_coords = GridCoords::from(end);
+                            }
+
+                            // send push events
+                            for (i, (pusher, _)) in moved_entities.iter().enumerate() {
+                                let pushed = &moved_entities[i + 1..];
+
+                                if !pushed.is_empty() {
+                                    if let (.., Some(_), _) = grid_coords_query
+                                        .get(*pusher)
+                                        .expect("pusher should be valid sokoban entity")
+                                    {
+                                        push_events.send(PushEvent {
+                                            pusher: *pusher,
+                                            direction: *direction,
+                                            pushed: pushed
+                                                .iter()
+                                                .map(|(entity, _)| *entity)
+                                                .collect(),
+                                            paths: paths[i + 1..].to_vec(),
+                                        });
+                                    }
+                                }
+                            }
+                        }
+                        Err(BlockedMove::PushRule(blocked)) => {
+                            move_blocked_events.send(MoveBlocked {
+                                entity: *entity,
+                                direction: *direction,
+                                blocker: blocked.first().copied(),
+                            });
+
+                            if let Ok((.., Some(_), _)) = grid_coords_query.get(*entity) {
+                                push_blocked_events.send(PushBlocked {
+                                    pusher: *entity,
+                                    direction: *direction,
+                                    blocked,
+                                });
+                            }
+                        }
+                        Err(BlockedMove::Collision(blocker)) => {
+                            move_blocked_events.send(MoveBlocked {
+                                entity: *entity,
+                                direction: *direction,
+                                blocker,
+                            });
+                        }
+                    }
+                }
+            }
+
+            grid.relocate(layer, &offsets);
+        }
+    } else {
+        warn!(
+            "could not find {} layer specified by SokobanLayerIdentifier resource",
+            **layer_id
+        );
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use bevy::ecs::system::SystemState;
+
+    pub(crate) fn app_setup() -> App {
+        #[derive(Clone, PartialEq, Eq, Debug, Default, Hash, States)]
+        enum State {
+            #[default]
+            Only,
+        }
+
+        let mut app = App::new();
+
+        app.add_state::<State>()
+            .add_plugins(SokobanPlugin::new(State::Only, "MyLayerIdentifier"));
+
+        app.world.spawn(LayerMetadata {
+            c_wid: 3,
+            c_hei: 4,
+            grid_size: 32,
+            identifier: "MyLayerIdentifier".to_string(),
+            ..default()
+        });
+
+        app
+    }
+
+    #[test]
+    fn push_with_sokoban_commands() {
+        let mut app = app_setup();
+
+        let block_a = app
+            .world
+            .spawn((GridCoords::new(1, 1), SokobanBlock::Dynamic))
+            .id();
+        let block_b = app
+            .world
+            .spawn((GridCoords::new(1, 2), SokobanBlock::Dynamic))
+            .id();
+        let block_c = app
+            .world
+            .spawn((GridCoords::new(2, 2), SokobanBlock::Dynamic))
+            .id();
+
+        let mut system_state: SystemState<SokobanCommands> = SystemState::new(&mut app.world);
+        let mut sokoban_commands: SokobanCommands = system_state.get_mut(&mut app.world);
+
+        sokoban_commands.move_block(block_a, super::Direction::Up);
+        sokoban_commands.move_block(block_c, super::Direction::Left);
+
+        system_state.apply(&mut app.world);
+
+        app.update();
+
+        assert_eq!(
+            *app.world.entity(block_a).get::<GridCoords>().unwrap(),
+            GridCoords::new(0, 2)
+        );
+        assert_eq!(
+            *app.world.entity(block_b).get::<GridCoords>().unwrap(),
+            GridCoords::new(1, 3)
+        );
+        assert_eq!(
+            *app.world.entity(block_c).get::<GridCoords>().unwrap(),
+            GridCoords::new(1, 2)
+        );
+    }
+
+    #[test]
+    fn push_multi_cell_block_with_sokoban_commands() {
+        let mut app = app_setup();
+
+        let block = app
+            .world
+            .spawn((GridCoords::new(0, 0), SokobanBlock::Dynamic))
+            .id();
+        let coffin = app
+            .world
+            .spawn((
+                GridCoords::new(1, 1),
+                SokobanBlock::Dynamic,
+                Footprint::rectangle(IVec2::new(1, 2)),
+            ))
+            .id();
+
+        let mut system_state: SystemState<SokobanCommands> = SystemState::new(&mut app.world);
+        let mut sokoban_commands: SokobanCommands = system_state.get_mut(&mut app.world);
+
+        // the block pushes the coffin by its bottom cell, then the coffin is blocked by the
+        // boundary as a whole
+        sokoban_commands.move_block(block, super::Direction::Right);
+        sokoban_commands.move_block(coffin, super::Direction::Right);
+        sokoban_commands.move_block(coffin, super::Direction::Up);
+
+        system_state.apply(&mut app.world);
+
+        app.update();
+
+        assert_eq!(
+            *app.world.entity(block).get::<GridCoords>().unwrap(),
+            GridCoords::new(1, 0)
+        );
+        assert_eq!(
+            *app.world.entity(coffin).get::<GridCoords>().unwrap(),
+            GridCoords::new(2, 2)
+        );
+    }
+
+    #[test]
+    fn push_rule_limits_pushes() {
+        let weights = [Weight(1), Weight(2)];
+
+        assert!(PushRule::Strength.allows_push(None, weights));
+        assert!(PushRule::Strength.allows_push(Some(Strength(3)), weights));
+        assert!(!PushRule::Strength.allows_push(Some(Strength(2)), weights));
+
+        assert!(PushRule::SingleBlock.allows_push(None, [Weight(5)]));
+        assert!(!PushRule::SingleBlock.allows_push(Some(Strength(3)), weights));
+    }
+}
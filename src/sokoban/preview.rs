@@ -0,0 +1,213 @@
+//! Read-only previews of sokoban moves.
+use crate::sokoban::{
+    mover_coords, resolve_move, BlockedMove, DiagonalSqueeze, Direction, Footprint, PushLimits,
+    SokobanBlock, SokobanGrid, Terrain,
+};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_ecs_ldtk::prelude::*;
+
+/// The outcome of a move previewed by [SokobanPreview].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MovePreview {
+    /// The move would succeed, moving these [SokobanBlock] entities along their paths.
+    ///
+    /// The mover comes first, followed by the blocks it would push from nearest to furthest.
+    /// Like in [PushEvent](super::PushEvent)s, every path ends at the block's final `GridCoords`.
+    Moved(Vec<(Entity, Vec<GridCoords>)>),
+    /// The move would be blocked, see [MoveBlocked](super::MoveBlocked).
+    Blocked {
+        /// The [SokobanBlock] entity that would block the move, if any.
+        blocker: Option<Entity>,
+    },
+}
+
+impl MovePreview {
+    /// The `GridCoords` the given entity would end up at, or `None` if it wouldn't move.
+    pub fn destination(&self, entity: Entity) -> Option<GridCoords> {
+        match self {
+            MovePreview::Moved(moved_entities) => moved_entities
+                .iter()
+                .find(|(moved_entity, _)| *moved_entity == entity)
+                .and_then(|(_, path)| path.last().copied()),
+            MovePreview::Blocked { .. } => None,
+        }
+    }
+}
+
+/// Read-only system parameter for previewing
+/// [SokobanCommands::move_block](super::SokobanCommands::move_block).
+///
+/// Previews are resolved against the current [SokobanGrid] with the same logic as the actual move,
+/// but no `GridCoords` are changed and no events are sent.
+#[derive(SystemParam)]
+pub struct SokobanPreview<'w, 's> {
+    grid: Res<'w, SokobanGrid>,
+    block_query:
+        Query<'w, 's, (&'static GridCoords, Option<&'static Footprint>), With<SokobanBlock>>,
+    terrain: Res<'w, Terrain>,
+    push_limits: PushLimits<'w, 's>,
+    diagonal_squeeze: Res<'w, DiagonalSqueeze>,
+}
+
+impl SokobanPreview<'_, '_> {
+    /// Previews moving a [SokobanBlock] entity in the given direction.
+    ///
+    /// Entities that aren't in the [SokobanGrid] are previewed as blocked.
+    pub fn preview(&self, entity: Entity, direction: Direction) -> MovePreview {
+        self.preview_moves(entity, [direction])
+            .pop()
+            .expect("a single move should have a single preview")
+    }
+
+    /// Previews moving a [SokobanBlock] entity in each of the given directions in turn.
+    ///
+    /// Every move is previewed as if the previous ones had been performed, like several
+    /// [SokobanCommands](super::SokobanCommands) sent in the same frame.
+    pub fn preview_moves(
+        &self,
+        entity: Entity,
+        directions: impl IntoIterator<Item = Direction>,
+    ) -> Vec<MovePreview> {
+        let directions = directions.into_iter();
+
+        let (Ok((grid_coords, footprint)), Some(collision_map)) = (
+            self.block_query.get(entity),
+            self.grid
+                .blocks
+                .get(&entity)
+                .and_then(|block| self.grid.collision_map(block.layer)),
+        ) else {
+            return directions
+                .map(|_| MovePreview::Blocked { blocker: None })
+                .collect();
+        };
+
+        let mut collision_map = collision_map.clone();
+
+        // multi-cell movers keep moving by the same cell
+        let mover_offset = mover_coords(grid_coords, footprint) - IVec2::from(*grid_coords);
+        let mut grid_coords = IVec2::from(*grid_coords);
+
+        directions
+            .map(|direction| {
+                let move_result = resolve_move(
+                    &mut collision_map,
+                    &self.terrain,
+                    &self.grid.footprints,
+                    entity,
+                    grid_coords + mover_offset,
+                    direction,
+                    *self.diagonal_squeeze,
+                    &self.push_limits,
+                );
+
+                match move_result {
+                    Ok(moved_entities) => {
+                        grid_coords = *moved_entities[0]
+                            .1
+                            .last()
+                            .expect("moved entity should have moved at least once");
+
+                        MovePreview::Moved(
+                            moved_entities
+                                .into_iter()
+                                .map(|(entity, path)| {
+                                    (entity, path.into_iter().map(GridCoords::from).collect())
+                                })
+                                .collect(),
+                        )
+                    }
+                    Err(BlockedMove::Collision(blocker)) => MovePreview::Blocked { blocker },
+                    Err(BlockedMove::PushRule(blocked)) => MovePreview::Blocked {
+                        blocker: blocked.first().copied(),
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sokoban::{tests::app_setup, MoveBlocked, PushEvent, PushTracker, Strength};
+    use bevy::ecs::system::SystemState;
+
+    #[test]
+    fn preview_does_not_move_blocks() {
+        let mut app = app_setup();
+
+        let block_a = app
+            .world
+            .spawn((
+                GridCoords::new(1, 0),
+                SokobanBlock::Dynamic,
+                PushTracker,
+                Strength(1),
+            ))
+            .id();
+        let block_b = app
+            .world
+            .spawn((GridCoords::new(1, 1), SokobanBlock::Dynamic, PushTracker))
+            .id();
+        let block_c = app
+            .world
+            .spawn((GridCoords::new(1, 2), SokobanBlock::Dynamic))
+            .id();
+        let wall = app
+            .world
+            .spawn((GridCoords::new(2, 1), SokobanBlock::Static))
+            .id();
+
+        app.update();
+
+        let mut system_state: SystemState<SokobanPreview> = SystemState::new(&mut app.world);
+        let sokoban_preview: SokobanPreview = system_state.get(&app.world);
+
+        let preview = sokoban_preview.preview(block_b, super::Direction::Up);
+
+        assert_eq!(
+            preview,
+            MovePreview::Moved(vec![
+                (block_b, vec![GridCoords::new(1, 2)]),
+                (block_c, vec![GridCoords::new(1, 3)]),
+            ])
+        );
+        assert_eq!(preview.destination(block_c), Some(GridCoords::new(1, 3)));
+        assert_eq!(preview.destination(block_a), None);
+
+        // block_b and block_c are too heavy for block_a to push together
+        assert_eq!(
+            sokoban_preview.preview(block_a, super::Direction::Up),
+            MovePreview::Blocked {
+                blocker: Some(block_b)
+            }
+        );
+        assert_eq!(
+            sokoban_preview.preview(block_b, super::Direction::Right),
+            MovePreview::Blocked {
+                blocker: Some(wall)
+            }
+        );
+
+        // later moves are previewed after earlier ones
+        assert_eq!(
+            sokoban_preview.preview_moves(block_b, [super::Direction::Left, super::Direction::Up]),
+            vec![
+                MovePreview::Moved(vec![(block_b, vec![GridCoords::new(0, 1)])]),
+                MovePreview::Moved(vec![(block_b, vec![GridCoords::new(0, 2)])]),
+            ]
+        );
+
+        assert_eq!(
+            *app.world.entity(block_b).get::<GridCoords>().unwrap(),
+            GridCoords::new(1, 1)
+        );
+        assert_eq!(
+            *app.world.entity(block_c).get::<GridCoords>().unwrap(),
+            GridCoords::new(1, 2)
+        );
+        assert!(app.world.resource::<Events<PushEvent>>().is_empty());
+        assert!(app.world.resource::<Events<MoveBlocked>>().is_empty());
+    }
+}
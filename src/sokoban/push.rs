@@ -0,0 +1,1519 @@
+//! Push resolution on collision maps, independent of the ECS.
+use crate::sokoban::{
+    bounding_box_top_left, entry_cells, grid_cell, is_multi_cell, Direction, Footprints,
+    SokobanBlock, Terrain,
+};
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+/// Resource defining whether diagonal pushes can squeeze between two static blocks.
+///
+/// A diagonal push "squeezes" when the entries on both sides of the diagonal, the ones in the
+/// orthogonal directions making it up, are [SokobanBlock::Static].
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug, Hash, Resource)]
+pub enum DiagonalSqueeze {
+    /// Diagonal pushes between two static blocks are blocked.
+    #[default]
+    Blocked,
+    /// Diagonal pushes between two static blocks are allowed.
+    Allowed,
+}
+
+/// Resource defining how much a single push can move.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug, Hash, Resource)]
+pub enum PushRule {
+    /// Pushers can push any number of blocks, as long as their total [Weight] doesn't exceed the
+    /// pusher's [Strength].
+    #[default]
+    Strength,
+    /// Pushers can push at most one block at a time, like in classic sokoban.
+    ///
+    /// [Strength] and [Weight] are ignored.
+    SingleBlock,
+}
+
+impl PushRule {
+    /// Returns `true` if a pusher with the given [Strength] can push blocks with the given
+    /// [Weight]s all at once.
+    ///
+    /// Pushers without strength can push any weight.
+    pub fn allows_push(
+        self,
+        strength: Option<Strength>,
+        weights: impl IntoIterator<Item = Weight>,
+    ) -> bool {
+        match self {
+            PushRule::Strength => {
+                let total_weight = weights
+                    .into_iter()
+                    .fold(0, |total: u32, Weight(weight)| total.saturating_add(weight));
+
+                strength.is_none_or(|Strength(strength)| total_weight <= strength)
+            }
+            PushRule::SingleBlock => weights.into_iter().count() <= 1,
+        }
+    }
+}
+
+/// Component limiting the total [Weight] of the blocks a [SokobanBlock] can push at once.
+///
+/// Blocks without strength can push any weight.
+/// Only used with [PushRule::Strength].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Component)]
+pub struct Strength(pub u32);
+
+impl Default for Strength {
+    fn default() -> Strength {
+        Strength(u32::MAX)
+    }
+}
+
+/// Component defining how much strength it takes to push a [SokobanBlock], see [Strength].
+///
+/// Blocks without weight weigh 1.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Component)]
+pub struct Weight(pub u32);
+
+impl Default for Weight {
+    fn default() -> Weight {
+        Weight(1)
+    }
+}
+
+/// Grid of [SokobanBlock]s, indexed by `[y][x]`, used to resolve pushes.
+///
+/// Entries are identified by `T`, which is the [Entity] of the block for the plugin's own systems.
+/// Other identifiers are useful for resolving pushes outside of the ECS, like in
+/// [crate::graveyard::simulation].
+pub type CollisionMap<T = Entity> = Vec<Vec<Option<(T, SokobanBlock)>>>;
+
+pub(super) fn collision_map_entry<T: Copy>(
+    collision_map: &CollisionMap<T>,
+    coords: IVec2,
+) -> Option<Option<(T, SokobanBlock)>> {
+    grid_cell(collision_map, coords).copied()
+}
+
+/// Returns `true` if a diagonal move from the given coordinates is blocked by static entries on
+/// both sides of it.
+fn is_squeezed<T: Copy>(
+    collision_map: &CollisionMap<T>,
+    coords: IVec2,
+    direction: Direction,
+    diagonal_squeeze: DiagonalSqueeze,
+) -> bool {
+    let is_static = |direction| {
+        matches!(
+            collision_map_entry(collision_map, coords + IVec2::from(direction)),
+            Some(Some((_, SokobanBlock::Static)))
+        )
+    };
+
+    diagonal_squeeze == DiagonalSqueeze::Blocked
+        && direction
+            .orthogonal_components()
+            .is_some_and(|(vertical, horizontal)| is_static(vertical) && is_static(horizontal))
+}
+
+/// The entry blocking a squeezed diagonal move from the given coordinates, which is the one in
+/// the move's vertical direction.
+fn squeezing_entry<T: Copy>(
+    collision_map: &CollisionMap<T>,
+    coords: IVec2,
+    direction: Direction,
+) -> Option<T> {
+    let (vertical, _) = direction.orthogonal_components()?;
+
+    collision_map_entry(collision_map, coords + IVec2::from(vertical))
+        .flatten()
+        .map(|(entry, _)| entry)
+}
+
+/// Pushes the entry at the given coordinates in the collision_map in the given direction.
+///
+/// If possible, it will also push any entries it collides with.
+///
+/// Entries occupying several cells, as looked up in `footprints`, are pushed as a whole.
+/// Either all of their cells move, or none of them do.
+///
+/// Diagonal pushes of an entry between two [SokobanBlock::Static] entries are only possible with
+/// [DiagonalSqueeze::Allowed].
+///
+/// Entries pushed into a [SokobanBlock::Hole] fall in, leaving both of their entries empty.
+/// The pusher itself can't move into a hole.
+///
+/// Entries moving into a [Teleporter](super::Teleporter) cell arrive on its partner instead,
+/// pushing the entry there in the same direction.
+/// Pushes that would lead back into themselves through teleporters are blocked.
+///
+/// Entries can't move into [OneWay](super::OneWay) cells against their direction.
+///
+/// # Returns
+/// Returns an optional list of pushed entities, updating the collision_map in place.
+/// Pushed entities are listed from the front of the push to the pusher.
+///
+/// If the optional list is `None`, no entities were pushed due to collision with either a
+/// [SokobanBlock::Static] entry or a boundary of the map, and the collision_map is unchanged.
+///
+/// If the optional list is empty, no entities were pushed due to the provided coordinates pointing
+/// to an empty entry. This distinction is important for the recursive algorithm.
+pub fn push_collision_map_entry<T: Copy + PartialEq>(
+    collision_map: &mut CollisionMap<T>,
+    terrain: &Terrain,
+    footprints: &impl Footprints<T>,
+    pusher_coords: IVec2,
+    direction: Direction,
+    diagonal_squeeze: DiagonalSqueeze,
+) -> Option<Vec<T>> {
+    push_entry(
+        collision_map,
+        terrain,
+        footprints,
+        pusher_coords,
+        direction,
+        diagonal_squeeze,
+        &mut PushProgress::default(),
+    )
+    .ok()
+    .map(|pushed| pushed.into_iter().map(|(entry, _)| entry).collect())
+}
+
+/// Progress of a push resolved by [push_entry].
+struct PushProgress<T> {
+    /// The coordinates of the entries pushing the current one, so it is empty for the mover.
+    chain: Vec<IVec2>,
+    /// The previous contents of every collision_map entry the push changed, oldest first.
+    ///
+    /// Blocked pushes are undone with it, rather than with a copy of the whole collision_map.
+    undo_log: Vec<(IVec2, Option<(T, SokobanBlock)>)>,
+}
+
+impl<T> Default for PushProgress<T> {
+    fn default() -> Self {
+        PushProgress {
+            chain: Vec::new(),
+            undo_log: Vec::new(),
+        }
+    }
+}
+
+impl<T: Copy> PushProgress<T> {
+    /// Sets the collision_map entry at the given coordinates, logging its previous contents.
+    fn set(
+        &mut self,
+        collision_map: &mut CollisionMap<T>,
+        coords: IVec2,
+        entry: Option<(T, SokobanBlock)>,
+    ) {
+        let cell = &mut collision_map[coords.y as usize][coords.x as usize];
+        self.undo_log.push((coords, std::mem::replace(cell, entry)));
+    }
+
+    /// Undoes every change logged after the first `len` changes.
+    fn undo(&mut self, collision_map: &mut CollisionMap<T>, len: usize) {
+        for (coords, entry) in self.undo_log.drain(len..).rev() {
+            collision_map[coords.y as usize][coords.x as usize] = entry;
+        }
+    }
+}
+
+/// Recursive implementation of [push_collision_map_entry].
+///
+/// Pushed entries are returned along with their new coordinates, which are the top-left corner of
+/// the bounding box of their cells.
+///
+/// Blocked pushes return the entry that blocked them, like a [SokobanBlock::Static] entry or a
+/// [SokobanBlock::Hole] in front of the mover.
+/// The blocker is `None` if the push was blocked by the boundary of the map or by terrain.
+fn push_entry<T: Copy + PartialEq>(
+    collision_map: &mut CollisionMap<T>,
+    terrain: &Terrain,
+    footprints: &impl Footprints<T>,
+    pusher_coords: IVec2,
+    direction: Direction,
+    diagonal_squeeze: DiagonalSqueeze,
+    progress: &mut PushProgress<T>,
+) -> Result<Vec<(T, IVec2)>, Option<T>> {
+    // check if pusher is out-of-bounds
+    if pusher_coords.x < 0
+        || pusher_coords.y < 0
+        || pusher_coords.y as usize >= collision_map.len()
+        || pusher_coords.x as usize >= collision_map[0].len()
+    {
+        // no updates to collision_map, no pushes can be performed
+        return Err(None);
+    }
+
+    // match against the pusher's CollisionMap entry
+    match collision_map[pusher_coords.y as usize][pusher_coords.x as usize] {
+        Some((pusher, SokobanBlock::Dynamic)) => {
+            if is_multi_cell(footprints, pusher) {
+                return push_multi_cell_entry(
+                    collision_map,
+                    terrain,
+                    footprints,
+                    pusher_coords,
+                    direction,
+                    diagonal_squeeze,
+                    progress,
+                );
+            }
+
+            // diagonal pushes can't squeeze between two static entries, unless configured to
+            if is_squeezed(collision_map, pusher_coords, direction, diagonal_squeeze) {
+                return Err(squeezing_entry(collision_map, pusher_coords, direction));
+            }
+
+            // pusher is dynamic, so we try to push
+            let destination = terrain.destination(pusher_coords, direction);
+
+            // teleporters may lead back into the push, and one-way cells may forbid it
+            if destination == pusher_coords
+                || progress.chain.contains(&destination)
+                || !terrain.allows_entry(destination, direction)
+            {
+                return Err(None);
+            }
+
+            // pushed entries fall into holes, consuming both
+            if !progress.chain.is_empty()
+                && matches!(
+                    collision_map_entry(collision_map, destination),
+                    Some(Some((_, SokobanBlock::Hole)))
+                )
+            {
+                progress.set(collision_map, destination, None);
+                progress.set(collision_map, pusher_coords, None);
+
+                return Ok(vec![(pusher, destination)]);
+            }
+
+            progress.chain.push(pusher_coords);
+
+            // if the destination can't be pushed, the pusher can't be pushed either
+            let mut pushed_entities = push_entry(
+                collision_map,
+                terrain,
+                footprints,
+                destination,
+                direction,
+                diagonal_squeeze,
+                progress,
+            )?;
+
+            // destination is either empty or has been pushed, so we can push the pusher
+            let entry = collision_map[pusher_coords.y as usize][pusher_coords.x as usize];
+            progress.set(collision_map, pusher_coords, None);
+            progress.set(collision_map, destination, entry);
+            pushed_entities.push((pusher, destination));
+
+            Ok(pushed_entities)
+        }
+        // pusher is static or a hole, no pushes can be performed, and it blocks the entries
+        // pushing it
+        Some((blocker, SokobanBlock::Static | SokobanBlock::Hole)) => {
+            Err((!progress.chain.is_empty()).then_some(blocker))
+        }
+        // pusher's entry is empty, no push is performed here but the caller is able to
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Implementation of [push_entry] for entries occupying several cells.
+///
+/// Every cell is pushed straight ahead, so teleporters don't affect the entry, and holes block it.
+/// The collision_map is only updated if every cell can move.
+fn push_multi_cell_entry<T: Copy + PartialEq>(
+    collision_map: &mut CollisionMap<T>,
+    terrain: &Terrain,
+    footprints: &impl Footprints<T>,
+    pusher_coords: IVec2,
+    direction: Direction,
+    diagonal_squeeze: DiagonalSqueeze,
+    progress: &mut PushProgress<T>,
+) -> Result<Vec<(T, IVec2)>, Option<T>> {
+    let Some(Some((pusher, _))) = collision_map_entry(collision_map, pusher_coords) else {
+        return Ok(Vec::new());
+    };
+
+    let cells = entry_cells(collision_map, footprints, pusher, pusher_coords);
+
+    // pushes branch out from each cell, so earlier branches are undone if a later one is blocked
+    let undo_len = progress.undo_log.len();
+    let mut pushed_entities = Vec::new();
+
+    progress.chain.extend(&cells);
+    let chain_len = progress.chain.len();
+
+    for cell in cells.iter() {
+        let destination = *cell + IVec2::from(direction);
+
+        let blocker = if is_squeezed(collision_map, *cell, direction, diagonal_squeeze) {
+            Some(squeezing_entry(collision_map, *cell, direction))
+        } else if !terrain.allows_entry(destination, direction) {
+            Some(None)
+        } else {
+            match collision_map_entry(collision_map, destination) {
+                // the pusher's own cells move out of the way along with this one
+                Some(Some((entry, _))) if entry == pusher => continue,
+                // out-of-bounds
+                None => Some(None),
+                // a hole that the whole entry can't fall into
+                Some(Some((hole, SokobanBlock::Hole))) => Some(Some(hole)),
+                _ => {
+                    let pushed = push_entry(
+                        collision_map,
+                        terrain,
+                        footprints,
+                        destination,
+                        direction,
+                        diagonal_squeeze,
+                        progress,
+                    );
+                    progress.chain.truncate(chain_len);
+
+                    match pushed {
+                        Ok(mut pushed) => {
+                            pushed_entities.append(&mut pushed);
+                            None
+                        }
+                        Err(blocker) => Some(blocker),
+                    }
+                }
+            }
+        };
+
+        if let Some(blocker) = blocker {
+            progress.undo(collision_map, undo_len);
+            return Err(blocker);
+        }
+    }
+
+    // every destination is either empty or one of the pusher's own cells, so all cells can move
+    for cell in cells.iter() {
+        progress.set(collision_map, *cell, None);
+    }
+
+    let destinations: Vec<IVec2> = cells
+        .iter()
+        .map(|cell| *cell + IVec2::from(direction))
+        .collect();
+
+    for destination in destinations.iter() {
+        progress.set(
+            collision_map,
+            *destination,
+            Some((pusher, SokobanBlock::Dynamic)),
+        );
+    }
+
+    pushed_entities.push((
+        pusher,
+        bounding_box_top_left(&destinations).expect("multi-cell entry should have cells"),
+    ));
+
+    Ok(pushed_entities)
+}
+
+/// Pulls the entry at the given coordinates in the collision_map in the given direction.
+///
+/// The puller only moves into an empty entry, and drags the [SokobanBlock::Dynamic] entry directly
+/// behind it (if any) into the entry it leaves.
+/// Pullers go through [Teleporter](super::Teleporter)s like pushers do, but pulled entries don't
+/// follow them.
+/// Entries occupying several cells can neither pull nor be pulled.
+///
+/// # Returns
+/// Returns the result of the pull, updating the collision_map in place.
+///
+/// If the result is `None`, the puller couldn't move, either because it is static, empty, or its
+/// destination isn't empty.
+/// Otherwise, it contains the puller and the entry it pulled, if any.
+pub fn pull_collision_map_entry<T: Copy + PartialEq>(
+    collision_map: &mut CollisionMap<T>,
+    terrain: &Terrain,
+    footprints: &impl Footprints<T>,
+    puller_coords: IVec2,
+    direction: Direction,
+    diagonal_squeeze: DiagonalSqueeze,
+) -> Option<(T, Option<T>)> {
+    let destination = terrain.destination(puller_coords, direction);
+    let behind = puller_coords - IVec2::from(direction);
+
+    let puller = match collision_map_entry(collision_map, puller_coords) {
+        Some(Some((puller, SokobanBlock::Dynamic))) if !is_multi_cell(footprints, puller) => puller,
+        // puller is static, multi-cell, empty or out-of-bounds, no pull can be performed
+        _ => return None,
+    };
+
+    if !matches!(collision_map_entry(collision_map, destination), Some(None))
+        || !terrain.allows_entry(destination, direction)
+        || is_squeezed(collision_map, puller_coords, direction, diagonal_squeeze)
+    {
+        // destination is occupied, out-of-bounds, one-way or squeezed, and pullers never push
+        return None;
+    }
+
+    collision_map[destination.y as usize][destination.x as usize] =
+        collision_map[puller_coords.y as usize][puller_coords.x as usize].take();
+
+    let pulled = match collision_map_entry(collision_map, behind) {
+        Some(Some((pulled, SokobanBlock::Dynamic)))
+            if !is_squeezed(collision_map, behind, direction, diagonal_squeeze)
+                && !is_multi_cell(footprints, pulled) =>
+        {
+            collision_map[puller_coords.y as usize][puller_coords.x as usize] =
+                collision_map[behind.y as usize][behind.x as usize].take();
+
+            Some(pulled)
+        }
+        _ => None,
+    };
+
+    Some((puller, pulled))
+}
+
+/// Slides the entry at the given coordinates in the given direction while it is on a
+/// [Slippery](super::Slippery) cell and the next entry is empty.
+///
+/// # Returns
+/// Returns the coordinates the entry slid through, ending with its final position.
+/// The list is empty if the entry didn't slide.
+pub fn slide_collision_map_entry<T: Copy>(
+    collision_map: &mut CollisionMap<T>,
+    terrain: &Terrain,
+    coords: IVec2,
+    direction: Direction,
+    diagonal_squeeze: DiagonalSqueeze,
+) -> Vec<IVec2> {
+    let mut path = Vec::new();
+    let mut current = coords;
+
+    while matches!(
+        collision_map_entry(collision_map, current),
+        Some(Some((_, SokobanBlock::Dynamic)))
+    ) && terrain.is_slippery(current)
+    {
+        let destination = terrain.destination(current, direction);
+
+        // teleporters may lead back to where the slide started
+        if destination == current
+            || destination == coords
+            || path.contains(&destination)
+            || !matches!(collision_map_entry(collision_map, destination), Some(None))
+            || !terrain.allows_entry(destination, direction)
+            || is_squeezed(collision_map, current, direction, diagonal_squeeze)
+        {
+            break;
+        }
+
+        collision_map[destination.y as usize][destination.x as usize] =
+            collision_map[current.y as usize][current.x as usize].take();
+
+        current = destination;
+        path.push(current);
+    }
+
+    path
+}
+
+/// Moves the entry at the given coordinates in the collision_map in the given direction.
+///
+/// The move is resolved like [push_collision_map_entry], then every moved entry that ended on a
+/// [Slippery](super::Slippery) cell slides via [slide_collision_map_entry], starting from the front
+/// of the push.
+/// Entries occupying several cells don't slide.
+///
+/// # Returns
+/// Returns the list of moved entries, updating the collision_map in place.
+/// Moved entries are listed from the front of the push to the mover, along with the coordinates
+/// each of them moved through, ending with their final position.
+///
+/// If the move was blocked, the collision_map is unchanged, and the error holds the entry that
+/// blocked it, like a [SokobanBlock::Static] entry or a [SokobanBlock::Hole] in front of the
+/// mover.
+/// The blocker is `None` if the move was blocked by the boundary of the map or by terrain.
+pub fn move_collision_map_entry<T: Copy + PartialEq>(
+    collision_map: &mut CollisionMap<T>,
+    terrain: &Terrain,
+    footprints: &impl Footprints<T>,
+    mover_coords: IVec2,
+    direction: Direction,
+    diagonal_squeeze: DiagonalSqueeze,
+) -> Result<Vec<(T, Vec<IVec2>)>, Option<T>> {
+    move_entry(
+        collision_map,
+        terrain,
+        footprints,
+        mover_coords,
+        direction,
+        diagonal_squeeze,
+        |_| true,
+    )
+    .map_err(|blocked_move| match blocked_move {
+        BlockedMove::Collision(blocker) => blocker,
+        BlockedMove::PushRule(_) => unreachable!("every push should be allowed"),
+    })
+}
+
+/// Reasons a move resolved by [limited_move_collision_map_entry] can't be performed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlockedMove<T = Entity> {
+    /// The mover collided with the given entry, see [move_collision_map_entry].
+    Collision(Option<T>),
+    /// The [PushRule] doesn't allow the mover to push these entries, nearest first.
+    PushRule(Vec<T>),
+}
+
+/// Moves the entry at the given coordinates like [move_collision_map_entry], as long as the
+/// [PushRule] allows a mover with the given [Strength] to push the entries in its way.
+///
+/// The [Weight] of each pushed entry is given by `weight`.
+/// Moves the push rule doesn't allow leave the collision_map unchanged.
+pub fn limited_move_collision_map_entry<T: Copy + PartialEq>(
+    collision_map: &mut CollisionMap<T>,
+    terrain: &Terrain,
+    footprints: &impl Footprints<T>,
+    mover_coords: IVec2,
+    direction: Direction,
+    diagonal_squeeze: DiagonalSqueeze,
+    push_rule: PushRule,
+    strength: Option<Strength>,
+    weight: impl Fn(T) -> Weight,
+) -> Result<Vec<(T, Vec<IVec2>)>, BlockedMove<T>> {
+    move_entry(
+        collision_map,
+        terrain,
+        footprints,
+        mover_coords,
+        direction,
+        diagonal_squeeze,
+        |pushed| push_rule.allows_push(strength, pushed.iter().map(|entry| weight(*entry))),
+    )
+}
+
+/// Implementation of [move_collision_map_entry] and [limited_move_collision_map_entry].
+///
+/// `allows_push` is given the entries the mover would push, nearest first.
+fn move_entry<T: Copy + PartialEq>(
+    collision_map: &mut CollisionMap<T>,
+    terrain: &Terrain,
+    footprints: &impl Footprints<T>,
+    mover_coords: IVec2,
+    direction: Direction,
+    diagonal_squeeze: DiagonalSqueeze,
+    allows_push: impl FnOnce(&[T]) -> bool,
+) -> Result<Vec<(T, Vec<IVec2>)>, BlockedMove<T>> {
+    let mut progress = PushProgress::default();
+
+    let pushed = push_entry(
+        collision_map,
+        terrain,
+        footprints,
+        mover_coords,
+        direction,
+        diagonal_squeeze,
+        &mut progress,
+    )
+    .map_err(BlockedMove::Collision)?;
+
+    // the mover comes last
+    let blocked: Vec<T> = pushed
+        .iter()
+        .rev()
+        .skip(1)
+        .map(|(entry, _)| *entry)
+        .collect();
+
+    if !allows_push(&blocked) {
+        progress.undo(collision_map, 0);
+        return Err(BlockedMove::PushRule(blocked));
+    }
+
+    let mut moved = Vec::new();
+
+    for (entry, coords) in pushed {
+        let mut path = vec![coords];
+
+        if !is_multi_cell(footprints, entry) {
+            path.extend(slide_collision_map_entry(
+                collision_map,
+                terrain,
+                coords,
+                direction,
+                diagonal_squeeze,
+            ));
+        }
+
+        moved.push((entry, path));
+    }
+
+    Ok(moved)
+}
+
+/// Returns `true` if moving from `start` through `path` in the given direction skipped any cells,
+/// meaning it went through a [Teleporter](super::Teleporter).
+pub(super) fn is_teleport(start: IVec2, path: &[IVec2], direction: Direction) -> bool {
+    std::iter::once(&start)
+        .chain(path)
+        .zip(path)
+        .any(|(from, to)| *to - *from != IVec2::from(direction))
+}
+
+/// System parameter checking pushes against the [PushRule].
+#[derive(SystemParam)]
+pub(super) struct PushLimits<'w, 's> {
+    push_rule: Res<'w, PushRule>,
+    push_limit_query:
+        Query<'w, 's, (Option<&'static Strength>, Option<&'static Weight>), With<SokobanBlock>>,
+}
+
+impl PushLimits<'_, '_> {
+    fn strength(&self, entity: Entity) -> Option<Strength> {
+        self.push_limit_query
+            .get(entity)
+            .ok()
+            .and_then(|(strength, _)| strength.copied())
+    }
+
+    fn weight(&self, entity: Entity) -> Weight {
+        self.push_limit_query
+            .get(entity)
+            .ok()
+            .and_then(|(_, weight)| weight.copied())
+            .unwrap_or_default()
+    }
+}
+
+/// Moves the mover's collision map entry like [limited_move_collision_map_entry], with the
+/// mover's [Strength] and the pushed blocks' [Weight]s.
+///
+/// Returns the moved entities and their paths, starting with the mover.
+pub(super) fn resolve_move(
+    collision_map: &mut CollisionMap,
+    terrain: &Terrain,
+    footprints: &impl Footprints<Entity>,
+    mover: Entity,
+    mover_coords: IVec2,
+    direction: Direction,
+    diagonal_squeeze: DiagonalSqueeze,
+    push_limits: &PushLimits,
+) -> Result<Vec<(Entity, Vec<IVec2>)>, BlockedMove> {
+    let mut moved_entities = limited_move_collision_map_entry(
+        collision_map,
+        terrain,
+        footprints,
+        mover_coords,
+        direction,
+        diagonal_squeeze,
+        *push_limits.push_rule,
+        push_limits.strength(mover),
+        |entity| push_limits.weight(entity),
+    )?;
+
+    moved_entities.reverse();
+
+    Ok(moved_entities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sokoban::Footprint;
+    use std::collections::HashMap;
+
+    #[test]
+    fn push_dynamic_into_empty() {
+        let pusher = Entity::from_raw(0);
+
+        let mut collision_map = vec![vec![None; 3]; 3];
+        collision_map[1][1] = Some((pusher, SokobanBlock::Dynamic));
+
+        let mut expected_collision_map = vec![vec![None; 3]; 3];
+        expected_collision_map[2][1] = Some((pusher, SokobanBlock::Dynamic));
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(1, 1),
+                super::Direction::Up,
+                DiagonalSqueeze::Blocked
+            ),
+            Some(vec![pusher])
+        );
+        assert_eq!(collision_map, expected_collision_map);
+    }
+
+    #[test]
+    fn push_dynamic_into_static() {
+        let pusher = Entity::from_raw(0);
+        let wall = Entity::from_raw(1);
+
+        let mut collision_map = vec![vec![None; 3]; 3];
+        collision_map[2][1] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[1][1] = Some((wall, SokobanBlock::Static));
+
+        let mut new_collision_map = collision_map.clone();
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut new_collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(1, 2),
+                super::Direction::Down,
+                DiagonalSqueeze::Blocked
+            ),
+            None
+        );
+        assert_eq!(new_collision_map, collision_map);
+    }
+
+    #[test]
+    fn push_dynamic_into_boundary() {
+        let pusher = Entity::from_raw(0);
+
+        let mut collision_map = vec![vec![None; 3]; 3];
+        collision_map[0][0] = Some((pusher, SokobanBlock::Dynamic));
+
+        let mut new_collision_map = collision_map.clone();
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut new_collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(0, 0),
+                super::Direction::Left,
+                DiagonalSqueeze::Blocked
+            ),
+            None
+        );
+        assert_eq!(new_collision_map, collision_map);
+    }
+
+    #[test]
+    fn push_dynamic_into_dynamic_into_empty() {
+        let pusher = Entity::from_raw(0);
+        let pushed = Entity::from_raw(1);
+
+        let mut collision_map = vec![vec![None; 3]; 3];
+        collision_map[1][0] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[1][1] = Some((pushed, SokobanBlock::Dynamic));
+
+        let mut expected_collision_map = vec![vec![None; 3]; 3];
+        expected_collision_map[1][1] = Some((pusher, SokobanBlock::Dynamic));
+        expected_collision_map[1][2] = Some((pushed, SokobanBlock::Dynamic));
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(0, 1),
+                super::Direction::Right,
+                DiagonalSqueeze::Blocked
+            ),
+            Some(vec![pushed, pusher])
+        );
+        assert_eq!(collision_map, expected_collision_map);
+    }
+
+    #[test]
+    fn push_dynamic_into_dynamic_into_static() {
+        let pusher = Entity::from_raw(0);
+        let pushed = Entity::from_raw(1);
+        let wall = Entity::from_raw(2);
+
+        let mut collision_map = vec![vec![None; 3]; 3];
+        collision_map[2][0] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[2][1] = Some((pushed, SokobanBlock::Dynamic));
+        collision_map[2][2] = Some((wall, SokobanBlock::Static));
+
+        let mut new_collision_map = collision_map.clone();
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut new_collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(0, 2),
+                super::Direction::Right,
+                DiagonalSqueeze::Blocked
+            ),
+            None
+        );
+        assert_eq!(new_collision_map, collision_map);
+    }
+
+    #[test]
+    fn push_dynamic_into_dynamic_into_boundary() {
+        let pusher = Entity::from_raw(0);
+        let pushed = Entity::from_raw(1);
+
+        let mut collision_map = vec![vec![None; 3]; 3];
+        collision_map[1][1] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[2][1] = Some((pushed, SokobanBlock::Dynamic));
+
+        let mut new_collision_map = collision_map.clone();
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut new_collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(1, 1),
+                super::Direction::Up,
+                DiagonalSqueeze::Blocked
+            ),
+            None
+        );
+        assert_eq!(new_collision_map, collision_map);
+    }
+
+    #[test]
+    fn push_diagonal_into_dynamic_into_empty() {
+        let pusher = Entity::from_raw(0);
+        let pushed = Entity::from_raw(1);
+
+        let mut collision_map = vec![vec![None; 3]; 3];
+        collision_map[0][0] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[1][1] = Some((pushed, SokobanBlock::Dynamic));
+
+        let mut expected_collision_map = vec![vec![None; 3]; 3];
+        expected_collision_map[1][1] = Some((pusher, SokobanBlock::Dynamic));
+        expected_collision_map[2][2] = Some((pushed, SokobanBlock::Dynamic));
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(0, 0),
+                super::Direction::UpRight,
+                DiagonalSqueeze::Blocked
+            ),
+            Some(vec![pushed, pusher])
+        );
+        assert_eq!(collision_map, expected_collision_map);
+    }
+
+    #[test]
+    fn push_diagonal_between_statics() {
+        let pusher = Entity::from_raw(0);
+        let wall_a = Entity::from_raw(1);
+        let wall_b = Entity::from_raw(2);
+
+        let mut collision_map = vec![vec![None; 3]; 3];
+        collision_map[1][1] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[0][1] = Some((wall_a, SokobanBlock::Static));
+        collision_map[1][0] = Some((wall_b, SokobanBlock::Static));
+
+        let mut new_collision_map = collision_map.clone();
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut new_collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(1, 1),
+                super::Direction::DownLeft,
+                DiagonalSqueeze::Blocked
+            ),
+            None
+        );
+        assert_eq!(new_collision_map, collision_map.clone());
+
+        let mut expected_collision_map = collision_map.clone();
+        expected_collision_map[1][1] = None;
+        expected_collision_map[0][0] = Some((pusher, SokobanBlock::Dynamic));
+
+        let mut new_collision_map = collision_map.clone();
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut new_collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(1, 1),
+                super::Direction::DownLeft,
+                DiagonalSqueeze::Allowed
+            ),
+            Some(vec![pusher])
+        );
+        assert_eq!(new_collision_map, expected_collision_map);
+
+        // a single static neighbor never blocks the push
+        collision_map[1][0] = None;
+
+        let mut expected_collision_map = collision_map.clone();
+        expected_collision_map[1][1] = None;
+        expected_collision_map[0][0] = Some((pusher, SokobanBlock::Dynamic));
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(1, 1),
+                super::Direction::DownLeft,
+                DiagonalSqueeze::Blocked
+            ),
+            Some(vec![pusher])
+        );
+        assert_eq!(collision_map, expected_collision_map);
+    }
+
+    #[test]
+    fn push_dynamic_into_hole() {
+        let pusher = Entity::from_raw(0);
+        let pushed = Entity::from_raw(1);
+        let hole = Entity::from_raw(2);
+
+        let mut collision_map = vec![vec![None; 4]; 1];
+        collision_map[0][0] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[0][1] = Some((pushed, SokobanBlock::Dynamic));
+        collision_map[0][2] = Some((hole, SokobanBlock::Hole));
+
+        let mut expected_collision_map = vec![vec![None; 4]; 1];
+        expected_collision_map[0][1] = Some((pusher, SokobanBlock::Dynamic));
+
+        let pushed_entities = push_collision_map_entry(
+            &mut collision_map,
+            &Terrain::default(),
+            &(),
+            IVec2::new(0, 0),
+            super::Direction::Right,
+            DiagonalSqueeze::Blocked,
+        );
+
+        assert_eq!(
+            (collision_map.clone(), pushed_entities),
+            (expected_collision_map, Some(vec![pushed, pusher]))
+        );
+
+        // the filled hole no longer blocks the pusher
+        let mut expected_collision_map = vec![vec![None; 4]; 1];
+        expected_collision_map[0][2] = Some((pusher, SokobanBlock::Dynamic));
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(1, 0),
+                super::Direction::Right,
+                DiagonalSqueeze::Blocked
+            ),
+            Some(vec![pusher])
+        );
+        assert_eq!(collision_map, expected_collision_map);
+    }
+
+    #[test]
+    fn hole_blocks_mover() {
+        let mover = Entity::from_raw(0);
+        let hole = Entity::from_raw(1);
+
+        let mut collision_map = vec![vec![None; 3]; 1];
+        collision_map[0][0] = Some((mover, SokobanBlock::Dynamic));
+        collision_map[0][1] = Some((hole, SokobanBlock::Hole));
+
+        let mut new_collision_map = collision_map.clone();
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut new_collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(0, 0),
+                super::Direction::Right,
+                DiagonalSqueeze::Blocked
+            ),
+            None
+        );
+        assert_eq!(new_collision_map, collision_map);
+    }
+
+    #[test]
+    fn push_through_teleporter() {
+        let pusher = Entity::from_raw(0);
+        let pushed = Entity::from_raw(1);
+        let exit_blocker = Entity::from_raw(2);
+
+        let mut terrain = Terrain::new(IVec2::new(6, 1));
+        terrain.link_teleporters(IVec2::new(2, 0), IVec2::new(4, 0));
+
+        let mut collision_map = vec![vec![None; 6]; 1];
+        collision_map[0][0] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[0][1] = Some((pushed, SokobanBlock::Dynamic));
+        collision_map[0][4] = Some((exit_blocker, SokobanBlock::Dynamic));
+
+        let mut expected_collision_map = vec![vec![None; 6]; 1];
+        expected_collision_map[0][1] = Some((pusher, SokobanBlock::Dynamic));
+        expected_collision_map[0][4] = Some((pushed, SokobanBlock::Dynamic));
+        expected_collision_map[0][5] = Some((exit_blocker, SokobanBlock::Dynamic));
+
+        // the pushed block arrives on the partner teleporter, pushing the block there onwards
+        let moved = move_collision_map_entry(
+            &mut collision_map,
+            &terrain,
+            &(),
+            IVec2::new(0, 0),
+            super::Direction::Right,
+            DiagonalSqueeze::Blocked,
+        );
+
+        assert_eq!(
+            (collision_map.clone(), moved),
+            (
+                expected_collision_map,
+                Ok(vec![
+                    (exit_blocker, vec![IVec2::new(5, 0)]),
+                    (pushed, vec![IVec2::new(4, 0)]),
+                    (pusher, vec![IVec2::new(1, 0)]),
+                ])
+            )
+        );
+
+        // the exit can't be pushed into any further
+        let mut new_collision_map = collision_map.clone();
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut new_collision_map,
+                &terrain,
+                &(),
+                IVec2::new(1, 0),
+                super::Direction::Right,
+                DiagonalSqueeze::Blocked
+            ),
+            None
+        );
+        assert_eq!(new_collision_map, collision_map);
+    }
+
+    #[test]
+    fn push_through_teleporter_into_itself_is_blocked() {
+        let pusher = Entity::from_raw(0);
+        let pushed = Entity::from_raw(1);
+
+        // the teleporter in front of the pushed block leads back to the pusher
+        let mut terrain = Terrain::new(IVec2::new(4, 1));
+        terrain.link_teleporters(IVec2::new(1, 0), IVec2::new(3, 0));
+
+        let mut collision_map = vec![vec![None; 4]; 1];
+        collision_map[0][1] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[0][2] = Some((pushed, SokobanBlock::Dynamic));
+
+        let mut new_collision_map = collision_map.clone();
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut new_collision_map,
+                &terrain,
+                &(),
+                IVec2::new(1, 0),
+                super::Direction::Right,
+                DiagonalSqueeze::Blocked
+            ),
+            None
+        );
+        assert_eq!(new_collision_map, collision_map);
+    }
+
+    #[test]
+    fn push_into_one_way() {
+        let pusher = Entity::from_raw(0);
+        let blocked = Entity::from_raw(1);
+
+        let mut terrain = Terrain::new(IVec2::new(3, 1));
+        terrain.add_one_way(IVec2::new(1, 0), super::Direction::Right);
+
+        let mut collision_map = vec![vec![None; 3]; 1];
+        collision_map[0][0] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[0][2] = Some((blocked, SokobanBlock::Dynamic));
+
+        let mut expected_collision_map = vec![vec![None; 3]; 1];
+        expected_collision_map[0][1] = Some((pusher, SokobanBlock::Dynamic));
+        expected_collision_map[0][2] = Some((blocked, SokobanBlock::Dynamic));
+
+        // the one-way cell can be entered in its direction
+        let pushed_entities = push_collision_map_entry(
+            &mut collision_map,
+            &terrain,
+            &(),
+            IVec2::new(0, 0),
+            super::Direction::Right,
+            DiagonalSqueeze::Blocked,
+        );
+
+        assert_eq!(
+            (collision_map.clone(), pushed_entities),
+            (expected_collision_map, Some(vec![pusher]))
+        );
+
+        // and left in any direction
+        push_collision_map_entry(
+            &mut collision_map,
+            &terrain,
+            &(),
+            IVec2::new(1, 0),
+            super::Direction::Left,
+            DiagonalSqueeze::Blocked,
+        );
+
+        assert_eq!(collision_map[0][0], Some((pusher, SokobanBlock::Dynamic)));
+
+        // but not entered against its direction
+        collision_map[0][0] = None;
+
+        let mut new_collision_map = collision_map.clone();
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut new_collision_map,
+                &terrain,
+                &(),
+                IVec2::new(2, 0),
+                super::Direction::Left,
+                DiagonalSqueeze::Blocked
+            ),
+            None
+        );
+        assert_eq!(new_collision_map, collision_map);
+    }
+
+    #[test]
+    fn push_multi_cell_entry() {
+        let pusher = Entity::from_raw(0);
+        let coffin = Entity::from_raw(1);
+
+        let mut collision_map = vec![vec![None; 4]; 1];
+        collision_map[0][0] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[0][1] = Some((coffin, SokobanBlock::Dynamic));
+        collision_map[0][2] = Some((coffin, SokobanBlock::Dynamic));
+
+        let footprints = HashMap::from([(coffin, Footprint::rectangle(IVec2::new(2, 1)))]);
+
+        let mut expected_collision_map = vec![vec![None; 4]; 1];
+        expected_collision_map[0][1] = Some((pusher, SokobanBlock::Dynamic));
+        expected_collision_map[0][2] = Some((coffin, SokobanBlock::Dynamic));
+        expected_collision_map[0][3] = Some((coffin, SokobanBlock::Dynamic));
+
+        let mut new_collision_map = collision_map.clone();
+
+        assert_eq!(
+            move_collision_map_entry(
+                &mut new_collision_map,
+                &Terrain::default(),
+                &footprints,
+                IVec2::new(0, 0),
+                super::Direction::Right,
+                DiagonalSqueeze::Blocked
+            ),
+            Ok(vec![
+                (coffin, vec![IVec2::new(2, 0)]),
+                (pusher, vec![IVec2::new(1, 0)]),
+            ])
+        );
+        assert_eq!(new_collision_map, expected_collision_map.clone());
+
+        // the coffin is blocked by the boundary as a whole
+        let mut new_collision_map = expected_collision_map.clone();
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut new_collision_map,
+                &Terrain::default(),
+                &footprints,
+                IVec2::new(1, 0),
+                super::Direction::Right,
+                DiagonalSqueeze::Blocked
+            ),
+            None
+        );
+        assert_eq!(new_collision_map, expected_collision_map);
+    }
+
+    #[test]
+    fn push_multi_cell_entry_moves_atomically() {
+        let pusher = Entity::from_raw(0);
+        let coffin = Entity::from_raw(1);
+        let pushed = Entity::from_raw(2);
+        let wall = Entity::from_raw(3);
+
+        // a vertical coffin, with a block in front of its bottom cell and a wall in front of its
+        // top cell
+        let mut collision_map = vec![vec![None; 4]; 2];
+        collision_map[0][0] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[0][1] = Some((coffin, SokobanBlock::Dynamic));
+        collision_map[1][1] = Some((coffin, SokobanBlock::Dynamic));
+        collision_map[0][2] = Some((pushed, SokobanBlock::Dynamic));
+        collision_map[1][2] = Some((wall, SokobanBlock::Static));
+
+        let footprints = HashMap::from([(coffin, Footprint::rectangle(IVec2::new(1, 2)))]);
+
+        // the block in front of the coffin could move, but the coffin's other cell can't
+        let mut new_collision_map = collision_map.clone();
+
+        assert_eq!(
+            push_collision_map_entry(
+                &mut new_collision_map,
+                &Terrain::default(),
+                &footprints,
+                IVec2::new(0, 0),
+                super::Direction::Right,
+                DiagonalSqueeze::Blocked
+            ),
+            None
+        );
+        assert_eq!(new_collision_map, collision_map.clone());
+
+        collision_map[1][2] = None;
+
+        let mut expected_collision_map = vec![vec![None; 4]; 2];
+        expected_collision_map[0][1] = Some((pusher, SokobanBlock::Dynamic));
+        expected_collision_map[0][2] = Some((coffin, SokobanBlock::Dynamic));
+        expected_collision_map[1][2] = Some((coffin, SokobanBlock::Dynamic));
+        expected_collision_map[0][3] = Some((pushed, SokobanBlock::Dynamic));
+
+        // the coffin's GridCoords are the top-left corner of its cells
+        assert_eq!(
+            move_collision_map_entry(
+                &mut collision_map,
+                &Terrain::default(),
+                &footprints,
+                IVec2::new(0, 0),
+                super::Direction::Right,
+                DiagonalSqueeze::Blocked
+            ),
+            Ok(vec![
+                (pushed, vec![IVec2::new(3, 0)]),
+                (coffin, vec![IVec2::new(2, 1)]),
+                (pusher, vec![IVec2::new(1, 0)]),
+            ])
+        );
+        assert_eq!(collision_map, expected_collision_map);
+    }
+
+    #[test]
+    fn limited_moves_are_undone() {
+        let pusher = Entity::from_raw(0);
+        let pushed = Entity::from_raw(1);
+        let hole = Entity::from_raw(2);
+
+        let mut collision_map = vec![vec![None; 4]; 1];
+        collision_map[0][0] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[0][1] = Some((pushed, SokobanBlock::Dynamic));
+        collision_map[0][2] = Some((hole, SokobanBlock::Hole));
+
+        let mut new_collision_map = collision_map.clone();
+
+        // the pushed block would fall into the hole, but it is too heavy
+        assert_eq!(
+            limited_move_collision_map_entry(
+                &mut new_collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(0, 0),
+                super::Direction::Right,
+                DiagonalSqueeze::Blocked,
+                PushRule::Strength,
+                Some(Strength(1)),
+                |_| Weight(2),
+            ),
+            Err(BlockedMove::PushRule(vec![pushed]))
+        );
+        assert_eq!(new_collision_map, collision_map);
+
+        assert_eq!(
+            limited_move_collision_map_entry(
+                &mut new_collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(0, 0),
+                super::Direction::Right,
+                DiagonalSqueeze::Blocked,
+                PushRule::Strength,
+                Some(Strength(2)),
+                |_| Weight(2),
+            ),
+            Ok(vec![
+                (pushed, vec![IVec2::new(2, 0)]),
+                (pusher, vec![IVec2::new(1, 0)]),
+            ])
+        );
+        assert_eq!(
+            new_collision_map,
+            vec![vec![
+                None,
+                Some((pusher, SokobanBlock::Dynamic)),
+                None,
+                None
+            ]]
+        );
+    }
+
+    #[test]
+    fn blocked_moves_report_their_blocker() {
+        let pusher = Entity::from_raw(0);
+        let pushed = Entity::from_raw(1);
+        let wall = Entity::from_raw(2);
+        let hole = Entity::from_raw(3);
+
+        let mut collision_map = vec![vec![None; 4]; 2];
+        collision_map[0][0] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[0][1] = Some((pushed, SokobanBlock::Dynamic));
+        collision_map[0][2] = Some((wall, SokobanBlock::Static));
+        collision_map[1][2] = Some((hole, SokobanBlock::Hole));
+
+        let blocker = |coords, direction| {
+            let mut new_collision_map = collision_map.clone();
+
+            let blocker = move_collision_map_entry(
+                &mut new_collision_map,
+                &Terrain::default(),
+                &(),
+                coords,
+                direction,
+                DiagonalSqueeze::Blocked,
+            )
+            .err();
+
+            assert_eq!(new_collision_map, collision_map);
+
+            blocker
+        };
+
+        assert_eq!(
+            blocker(IVec2::new(0, 0), super::Direction::Right),
+            Some(Some(wall))
+        );
+        assert_eq!(
+            blocker(IVec2::new(1, 0), super::Direction::UpRight),
+            Some(Some(hole))
+        );
+        // moving out of bounds isn't blocked by an entity
+        assert_eq!(
+            blocker(IVec2::new(0, 0), super::Direction::Left),
+            Some(None)
+        );
+    }
+
+    #[test]
+    fn pull_dynamic_behind() {
+        let puller = Entity::from_raw(0);
+        let pulled = Entity::from_raw(1);
+
+        let mut collision_map = vec![vec![None; 3]; 3];
+        collision_map[1][1] = Some((puller, SokobanBlock::Dynamic));
+        collision_map[1][0] = Some((pulled, SokobanBlock::Dynamic));
+
+        let mut expected_collision_map = vec![vec![None; 3]; 3];
+        expected_collision_map[1][2] = Some((puller, SokobanBlock::Dynamic));
+        expected_collision_map[1][1] = Some((pulled, SokobanBlock::Dynamic));
+
+        assert_eq!(
+            pull_collision_map_entry(
+                &mut collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(1, 1),
+                super::Direction::Right,
+                DiagonalSqueeze::Blocked
+            ),
+            Some((puller, Some(pulled)))
+        );
+        assert_eq!(collision_map, expected_collision_map);
+    }
+
+    #[test]
+    fn pull_does_not_push_or_drag_static() {
+        let puller = Entity::from_raw(0);
+        let blocker = Entity::from_raw(1);
+        let wall = Entity::from_raw(2);
+
+        let mut collision_map = vec![vec![None; 3]; 3];
+        collision_map[1][1] = Some((puller, SokobanBlock::Dynamic));
+        collision_map[2][1] = Some((blocker, SokobanBlock::Dynamic));
+        collision_map[0][1] = Some((wall, SokobanBlock::Static));
+
+        // the puller can't push the block in front of it
+        let mut new_collision_map = collision_map.clone();
+
+        assert_eq!(
+            pull_collision_map_entry(
+                &mut new_collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(1, 1),
+                super::Direction::Up,
+                DiagonalSqueeze::Blocked
+            ),
+            None
+        );
+        assert_eq!(new_collision_map, collision_map.clone());
+
+        collision_map[2][1] = None;
+
+        let mut expected_collision_map = collision_map.clone();
+        expected_collision_map[1][1] = None;
+        expected_collision_map[2][1] = Some((puller, SokobanBlock::Dynamic));
+
+        // the wall behind the puller stays put
+        assert_eq!(
+            pull_collision_map_entry(
+                &mut collision_map,
+                &Terrain::default(),
+                &(),
+                IVec2::new(1, 1),
+                super::Direction::Up,
+                DiagonalSqueeze::Blocked
+            ),
+            Some((puller, None))
+        );
+        assert_eq!(collision_map, expected_collision_map);
+    }
+
+    #[test]
+    fn move_onto_slippery_slides_until_blocked() {
+        let pusher = Entity::from_raw(0);
+        let pushed = Entity::from_raw(1);
+        let wall = Entity::from_raw(2);
+
+        let mut collision_map = vec![vec![None; 5]; 1];
+        collision_map[0][0] = Some((pusher, SokobanBlock::Dynamic));
+        collision_map[0][1] = Some((pushed, SokobanBlock::Dynamic));
+        collision_map[0][4] = Some((wall, SokobanBlock::Static));
+
+        let terrain = Terrain {
+            slippery: vec![vec![false, true, true, false, false]],
+            ..default()
+        };
+
+        let mut expected_collision_map = vec![vec![None; 5]; 1];
+        expected_collision_map[0][2] = Some((pusher, SokobanBlock::Dynamic));
+        expected_collision_map[0][3] = Some((pushed, SokobanBlock::Dynamic));
+        expected_collision_map[0][4] = Some((wall, SokobanBlock::Static));
+
+        // the pushed block slides off the ice and stops against the wall, then the pusher slides
+        // into it
+        assert_eq!(
+            move_collision_map_entry(
+                &mut collision_map,
+                &terrain,
+                &(),
+                IVec2::new(0, 0),
+                super::Direction::Right,
+                DiagonalSqueeze::Blocked
+            ),
+            Ok(vec![
+                (pushed, vec![IVec2::new(2, 0), IVec2::new(3, 0)]),
+                (pusher, vec![IVec2::new(1, 0), IVec2::new(2, 0)]),
+            ])
+        );
+        assert_eq!(collision_map, expected_collision_map);
+    }
+}
@@ -0,0 +1,256 @@
+//! Cells that change how sokoban blocks move through them.
+use crate::sokoban::{Direction, SokobanBlock, SokobanGrid};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_ecs_ldtk::prelude::*;
+
+/// Component marking grid cells that [SokobanBlock::Dynamic] blocks slide across, like ice.
+///
+/// Blocks that end a move on a slippery cell keep moving in the same direction until they hit
+/// something.
+/// The entity should not be a [SokobanBlock] itself.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug, Hash, Component)]
+pub struct Slippery;
+
+/// Component marking teleporter cells, which relocate [SokobanBlock]s moving into them.
+///
+/// A block moving into a teleporter arrives on its partner instead, pushing whatever is there in
+/// the same direction.
+/// Teleporters are linked to their partners by LDtk iid, see [Terrain::add_teleporters].
+/// The entity should not be a [SokobanBlock] itself.
+#[derive(Clone, Default, Eq, PartialEq, Debug, Hash, Component)]
+pub struct Teleporter {
+    /// The LDtk iid of this teleporter.
+    pub iid: String,
+    /// The LDtk iid of the teleporter this one leads to, if any.
+    pub partner: Option<String>,
+}
+
+/// Component marking one-way cells, which blocks can only move into in the given direction.
+///
+/// Blocks can leave one-way cells in any direction.
+/// The entity should not be a [SokobanBlock] itself.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Component)]
+pub struct OneWay(pub Direction);
+
+/// Properties of the grid's cells that affect how [SokobanBlock]s move, indexed by `[y][x]`.
+///
+/// Cells outside of the grids are plain floor, so [Terrain::default] doesn't affect movement.
+///
+/// The plugin keeps the terrain of the current level in a resource, which is only rebuilt when
+/// [Slippery], [Teleporter] or [OneWay] entities change.
+#[derive(Clone, Default, Eq, PartialEq, Debug, Resource)]
+pub struct Terrain {
+    /// Whether or not each cell is [Slippery].
+    pub slippery: Vec<Vec<bool>>,
+    /// The cell that each [Teleporter] cell leads to.
+    pub teleporters: Vec<Vec<Option<IVec2>>>,
+    /// The only direction each [OneWay] cell can be entered in.
+    pub one_way: Vec<Vec<Option<Direction>>>,
+}
+
+pub(super) fn grid_cell<T>(grid: &[Vec<T>], coords: IVec2) -> Option<&T> {
+    grid.get(usize::try_from(coords.y).ok()?)?
+        .get(usize::try_from(coords.x).ok()?)
+}
+
+fn grid_cell_mut<T>(grid: &mut [Vec<T>], coords: IVec2) -> Option<&mut T> {
+    grid.get_mut(usize::try_from(coords.y).ok()?)?
+        .get_mut(usize::try_from(coords.x).ok()?)
+}
+
+impl Terrain {
+    /// Constructs plain floor with the given dimensions.
+    pub fn new(size: IVec2) -> Terrain {
+        let (width, height) = (size.x.max(0) as usize, size.y.max(0) as usize);
+
+        Terrain {
+            slippery: vec![vec![false; width]; height],
+            teleporters: vec![vec![None; width]; height],
+            one_way: vec![vec![None; width]; height],
+        }
+    }
+
+    /// Dimensions of the terrain.
+    pub(super) fn size(&self) -> IVec2 {
+        IVec2::new(
+            self.slippery.first().map_or(0, Vec::len) as i32,
+            self.slippery.len() as i32,
+        )
+    }
+
+    /// Makes the cell at the given coordinates [Slippery].
+    pub fn add_slippery(&mut self, coords: IVec2) {
+        if let Some(cell) = grid_cell_mut(&mut self.slippery, coords) {
+            *cell = true;
+        }
+    }
+
+    /// Makes the cell at the given coordinates [OneWay], only enterable in the given direction.
+    pub fn add_one_way(&mut self, coords: IVec2, direction: Direction) {
+        if let Some(cell) = grid_cell_mut(&mut self.one_way, coords) {
+            *cell = Some(direction);
+        }
+    }
+
+    /// Links the teleporter cells at the given coordinates, so that each leads to the other.
+    pub fn link_teleporters(&mut self, a: IVec2, b: IVec2) {
+        if let Some(cell) = grid_cell_mut(&mut self.teleporters, a) {
+            *cell = Some(b);
+        }
+
+        if let Some(cell) = grid_cell_mut(&mut self.teleporters, b) {
+            *cell = Some(a);
+        }
+    }
+
+    /// Links [Teleporter]s at the given coordinates to the partners named by their iids.
+    ///
+    /// Links go both ways, so only one teleporter of each pair needs to name the other.
+    pub fn add_teleporters<'a>(
+        &mut self,
+        teleporters: impl IntoIterator<Item = (IVec2, &'a Teleporter)>,
+    ) {
+        let teleporters: Vec<_> = teleporters.into_iter().collect();
+
+        for (coords, teleporter) in teleporters.iter() {
+            if let Some((partner_coords, _)) = teleporters
+                .iter()
+                .find(|(_, partner)| teleporter.partner.as_ref() == Some(&partner.iid))
+            {
+                self.link_teleporters(*coords, *partner_coords);
+            }
+        }
+    }
+
+    /// Returns `true` if the cell at the given coordinates is [Slippery].
+    pub fn is_slippery(&self, coords: IVec2) -> bool {
+        grid_cell(&self.slippery, coords).copied().unwrap_or(false)
+    }
+
+    /// Returns `true` if the cell at the given coordinates can be entered in the given direction.
+    pub fn allows_entry(&self, coords: IVec2, direction: Direction) -> bool {
+        grid_cell(&self.one_way, coords)
+            .copied()
+            .flatten()
+            .is_none_or(|one_way| one_way == direction)
+    }
+
+    /// The cell that an entry at the given coordinates moves into when moving in the given
+    /// direction.
+    ///
+    /// This is the neighboring cell, or its partner if the neighboring cell is a [Teleporter].
+    pub fn destination(&self, coords: IVec2, direction: Direction) -> IVec2 {
+        let neighbor = coords + IVec2::from(direction);
+
+        grid_cell(&self.teleporters, neighbor)
+            .copied()
+            .flatten()
+            .unwrap_or(neighbor)
+    }
+}
+
+/// System parameter collecting the [Terrain] of the level.
+#[derive(SystemParam)]
+pub(super) struct TerrainParam<'w, 's> {
+    slippery_query: Query<'w, 's, &'static GridCoords, (With<Slippery>, Without<SokobanBlock>)>,
+    teleporter_query:
+        Query<'w, 's, (&'static GridCoords, &'static Teleporter), Without<SokobanBlock>>,
+    one_way_query: Query<'w, 's, (&'static GridCoords, &'static OneWay), Without<SokobanBlock>>,
+}
+
+impl TerrainParam<'_, '_> {
+    fn terrain(&self, size: IVec2) -> Terrain {
+        let mut terrain = Terrain::new(size);
+
+        for grid_coords in self.slippery_query.iter() {
+            terrain.add_slippery(IVec2::from(*grid_coords));
+        }
+
+        for (grid_coords, OneWay(direction)) in self.one_way_query.iter() {
+            terrain.add_one_way(IVec2::from(*grid_coords), *direction);
+        }
+
+        terrain.add_teleporters(
+            self.teleporter_query
+                .iter()
+                .map(|(grid_coords, teleporter)| (IVec2::from(*grid_coords), teleporter)),
+        );
+
+        terrain
+    }
+}
+
+pub(super) fn sync_terrain(
+    mut terrain: ResMut<Terrain>,
+    terrain_param: TerrainParam,
+    changed_terrain_query: Query<
+        (),
+        (
+            Or<(
+                Changed<GridCoords>,
+                Changed<Slippery>,
+                Changed<Teleporter>,
+                Changed<OneWay>,
+            )>,
+            Or<(With<Slippery>, With<Teleporter>, With<OneWay>)>,
+            Without<SokobanBlock>,
+        ),
+    >,
+    mut removed_slippery: RemovedComponents<Slippery>,
+    mut removed_teleporters: RemovedComponents<Teleporter>,
+    mut removed_one_ways: RemovedComponents<OneWay>,
+    grid: Res<SokobanGrid>,
+) {
+    let removed = removed_slippery.read().count()
+        + removed_teleporters.read().count()
+        + removed_one_ways.read().count();
+
+    if removed > 0 || !changed_terrain_query.is_empty() || terrain.size() != grid.size() {
+        *terrain = terrain_param.terrain(grid.size());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sokoban::tests::app_setup;
+
+    #[test]
+    fn terrain_follows_terrain_entities() {
+        let mut app = app_setup();
+
+        let ice = app.world.spawn((GridCoords::new(1, 1), Slippery)).id();
+
+        app.update();
+
+        assert!(app
+            .world
+            .resource::<Terrain>()
+            .is_slippery(IVec2::new(1, 1)));
+
+        // the terrain is left alone while nothing changes
+        app.update();
+
+        assert!(!app.world.is_resource_changed::<Terrain>());
+
+        app.world.entity_mut(ice).insert(GridCoords::new(2, 1));
+        app.update();
+
+        assert!(!app
+            .world
+            .resource::<Terrain>()
+            .is_slippery(IVec2::new(1, 1)));
+        assert!(app
+            .world
+            .resource::<Terrain>()
+            .is_slippery(IVec2::new(2, 1)));
+
+        app.world.despawn(ice);
+        app.update();
+
+        assert_eq!(
+            *app.world.resource::<Terrain>(),
+            Terrain::new(IVec2::new(3, 4))
+        );
+    }
+}
@@ -16,6 +16,9 @@ pub struct TextButton;
 
 /// Spawns a text button with the provided `button_text`.
 ///
+/// The text and its underline decoration are tinted with `color`, which can be used to dim buttons
+/// that can't be pressed.
+///
 /// Returns [EntityCommands] for the button entity.
 /// You can use this to add more components if necessary.
 ///
@@ -27,6 +30,7 @@ pub fn spawn<'w, 's, 'a, S: Into<String>>(
     asset_holder: &AssetHolder,
     margin: Val,
     font_size: FontSize,
+    color: Color,
 ) -> EntityCommands<'w, 's, 'a> {
     // Assigning the initial spawn to a variable is important for being able to return the
     // EntityCommands
@@ -77,7 +81,7 @@ pub fn spawn<'w, 's, 'a, S: Into<String>>(
                 button_text,
                 TextStyle {
                     font: asset_holder.font.clone(),
-                    color: Color::WHITE,
+                    color,
                     ..default()
                 },
             ))
@@ -99,6 +103,7 @@ pub fn spawn<'w, 's, 'a, S: Into<String>>(
                 aspect_ratio: Some(4.),
                 ..default()
             },
+            background_color: BackgroundColor(color),
             focus_policy: FocusPolicy::Pass,
             ..default()
         });
@@ -1,5 +1,7 @@
 //! Common utilities that may be useful in many contexts.
+use crate::AssetHolder;
 use bevy::{ecs::query::ReadOnlyWorldQuery, prelude::*};
+use bevy_ecs_ldtk::prelude::*;
 
 /// Simple run condition that passes if the resource has changed.
 pub fn resource_changed<R: Resource>(resource: Res<R>) -> bool {
@@ -10,3 +12,15 @@ pub fn resource_changed<R: Resource>(resource: Res<R>) -> bool {
 pub fn any_match_filter<F: ReadOnlyWorldQuery>(filter_query: Query<(), F>) -> bool {
     !filter_query.is_empty()
 }
+
+/// The identifier of the selected level, if the LDtk project has loaded and contains it.
+pub fn current_level_identifier(
+    level_selection: &LevelSelection,
+    ldtk_assets: &Assets<LdtkProject>,
+    asset_holder: &AssetHolder,
+) -> Option<String> {
+    ldtk_assets
+        .get(&asset_holder.ldtk)?
+        .find_raw_level_by_level_selection(level_selection)
+        .map(|level| level.identifier.clone())
+}